@@ -1,7 +1,7 @@
 use crate::path::Path;
-use sede::{deserialize_rc_box_any_map, serialize_rc_box_any_map};
-use serde::Deserialize;
-use serde::Serialize;
+use sede::deserialize_rc_box_any_map;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 use std::any::Any;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
@@ -11,15 +11,26 @@ use std::str::FromStr;
 /// Represents the Result of a query made to the Export.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// Converts a value stored in an [Export] into its textual representation, if it has the expected type.
+type ValueSerializer = fn(&dyn Any) -> Option<String>;
+
+fn display<A: 'static + Display>(value: &dyn Any) -> Option<String> {
+    value.downcast_ref::<A>().map(|value| value.to_string())
+}
+
 /// Abstraction for the result of local computation.
 /// It is an AST decorated with the computation value.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Values of type i32, bool, String, f64 and the values of the [crate::geometry] module can always
+/// be serialized. Values of any other type can be serialized only if they are inserted with
+/// [Export::put_serializable], in which case they are sent as their [Display] representation and
+/// parsed back with [FromStr] when read.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Export {
-    #[serde(
-        serialize_with = "serialize_rc_box_any_map",
-        deserialize_with = "deserialize_rc_box_any_map"
-    )]
+    #[serde(deserialize_with = "deserialize_rc_box_any_map")]
     map: HashMap<Path, Rc<Box<dyn Any>>>,
+    #[serde(skip)]
+    serializers: HashMap<Path, ValueSerializer>,
 }
 
 #[macro_export]
@@ -42,6 +53,7 @@ impl Export {
     pub fn new() -> Self {
         Self {
             map: HashMap::new(),
+            serializers: HashMap::new(),
         }
     }
 
@@ -56,9 +68,26 @@ impl Export {
     ///
     /// * `A` - The type of the value to insert. It must have a `'static` lifetime.
     pub fn put<A: 'static>(&mut self, path: Path, value: A) {
+        self.serializers.remove(&path);
         self.map.insert(path, Rc::new(Box::new(value)));
     }
 
+    /// Inserts a value in the Export at the given Path, so that it can be serialized through its
+    /// [Display] representation whatever its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The Path where to insert the value.
+    /// * `value` - The value to insert.
+    ///
+    /// # Generic Parameters
+    ///
+    /// * `A` - The type of the value to insert. It must have a `'static` lifetime and implement [Display].
+    pub fn put_serializable<A: 'static + Display>(&mut self, path: Path, value: A) {
+        self.map.insert(path.clone(), Rc::new(Box::new(value)));
+        self.serializers.insert(path, display::<A>);
+    }
+
     /// Inserts a value in the Export at the given Path. The value is calculated from the provided
     /// function.
     ///
@@ -89,18 +118,18 @@ impl Export {
     ///
     /// # Generic Parameters
     ///
-    /// * `A` - The type of the value to insert. It must have a `'static` lifetime.
+    /// * `A` - The type of the value to insert. It must have a `'static` lifetime and implement [Display].
     /// * `F` - The type of the function from which the value is calculated.
     ///
     /// # Returns
     ///
     /// The calculated value.
-    pub fn put_lazy_and_return<A: 'static + Clone, F>(&mut self, path: Path, fun: F) -> A
+    pub fn put_lazy_and_return<A: 'static + Clone + Display, F>(&mut self, path: Path, fun: F) -> A
     where
         F: FnOnce() -> A,
     {
         let value = fun();
-        self.put(path, value.clone());
+        self.put_serializable(path, value.clone());
         value
     }
 
@@ -179,6 +208,36 @@ impl Export {
     pub fn paths(&self) -> &HashMap<Path, Rc<Box<dyn Any>>> {
         &self.map
     }
+
+    /// Returns the textual representation of the value at the given Path, which is how the value
+    /// is serialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The Path of the value.
+    ///
+    /// # Returns
+    ///
+    /// The textual representation of the value, or `None` if there is no value or it cannot be serialized.
+    pub fn serialized(&self, path: &Path) -> Option<String> {
+        let value: &dyn Any = self.map.get(path)?.as_ref().as_ref();
+        sede::native_to_string(value).or_else(|| {
+            self.serializers
+                .get(path)
+                .and_then(|serializer| serializer(value))
+        })
+    }
+}
+
+impl Serialize for Export {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Export", 1)?;
+        state.serialize_field("map", &sede::SerializableExport(self))?;
+        state.end()
+    }
 }
 
 impl Default for Export {
@@ -189,7 +248,10 @@ impl Default for Export {
 
 impl From<HashMap<Path, Rc<Box<dyn Any>>>> for Export {
     fn from(map: HashMap<Path, Rc<Box<dyn Any>>>) -> Self {
-        Self { map }
+        Self {
+            map,
+            serializers: HashMap::new(),
+        }
     }
 }
 
@@ -221,7 +283,8 @@ impl PartialEq for Export {
                 if let Ok(other_value) = other.get::<String>(key) {
                     value == other_value
                 } else {
-                    false
+                    // A deserialized value can be compared with the value it was serialized from
+                    other.serialized(key) == Some(value)
                 }
             } else if let Ok(value) = self.get::<f64>(key) {
                 if let Ok(other_value) = other.get::<f64>(key) {
@@ -230,7 +293,11 @@ impl PartialEq for Export {
                     false
                 }
             } else {
-                false
+                // Values of other types are compared through their textual representation
+                match (self.serialized(key), other.serialized(key)) {
+                    (Some(value), Some(other_value)) => value == other_value,
+                    _ => false,
+                }
             }
         });
 
@@ -240,40 +307,66 @@ impl PartialEq for Export {
 
 /// This private module is needed to serialize and deserialize the HashMap<Path, Rc<Box<dyn Any>>>.
 mod sede {
+    use super::Export;
+    use crate::geometry::{Point2D, Point3D, Vector2D, Vector3D};
     use crate::path::Path;
     use serde::de::Visitor;
+    use serde::ser::Error;
     use serde::{Deserializer, Serialize, Serializer};
     use std::any::Any;
     use std::collections::HashMap;
     use std::rc::Rc;
 
-    pub fn serialize_rc_box_any_map<S>(
-        data: &HashMap<Path, Rc<Box<dyn Any>>>,
-        serializer: S,
-    ) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        // Serialize the data and wrap it in a HashMap<Path, [u8]>
-        let serializable_data: HashMap<String, String> = data
-            .iter()
-            .map(|(key, value)| {
-                let key_str = serde_json::to_string(key).unwrap();
-                // if value is an i32, cast as String, otherwise panic
-                if let Some(value) = value.downcast_ref::<i32>() {
-                    (key_str, value.clone().to_string())
-                } else if let Some(value) = value.downcast_ref::<bool>() {
-                    (key_str, value.clone().to_string())
-                } else if let Some(value) = value.downcast_ref::<String>() {
-                    (key_str, value.clone())
-                } else if let Some(value) = value.downcast_ref::<f64>() {
-                    (key_str, value.clone().to_string())
-                } else {
-                    panic!("Cannot serialize type")
-                }
-            })
-            .collect();
-        serializable_data.serialize(serializer)
+    type ExportMap = HashMap<Path, Rc<Box<dyn Any>>>;
+
+    /// Wraps an [Export] to serialize its values as a HashMap<String, String>.
+    pub struct SerializableExport<'a>(pub &'a Export);
+
+    impl Serialize for SerializableExport<'_> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut serializable_data: HashMap<String, String> = HashMap::new();
+            for key in self.0.map.keys() {
+                let key_str = serde_json::to_string(key).map_err(S::Error::custom)?;
+                let value = self.0.serialized(key).ok_or_else(|| {
+                    S::Error::custom(format!("Cannot serialize value at {}", key))
+                })?;
+                serializable_data.insert(key_str, value);
+            }
+            serializable_data.serialize(serializer)
+        }
+    }
+
+    /// Converts the types that can always be serialized into their textual representation.
+    pub fn native_to_string(value: &dyn Any) -> Option<String> {
+        if let Some(value) = value.downcast_ref::<i32>() {
+            Some(value.to_string())
+        } else if let Some(value) = value.downcast_ref::<bool>() {
+            Some(value.to_string())
+        } else if let Some(value) = value.downcast_ref::<String>() {
+            Some(value.clone())
+        } else if let Some(value) = value.downcast_ref::<f64>() {
+            Some(value.to_string())
+        } else {
+            geometry_to_string(value)
+        }
+    }
+
+    /// Converts the values of the [crate::geometry] module into their textual representation.
+    fn geometry_to_string(value: &dyn Any) -> Option<String> {
+        if let Some(value) = value.downcast_ref::<Vector2D>() {
+            Some(value.to_string())
+        } else if let Some(value) = value.downcast_ref::<Vector3D>() {
+            Some(value.to_string())
+        } else if let Some(value) = value.downcast_ref::<Point2D>() {
+            Some(value.to_string())
+        } else {
+            value
+                .downcast_ref::<Point3D>()
+                .map(|value| value.to_string())
+        }
     }
 
     struct ExportMapVisitor;
@@ -298,9 +391,7 @@ mod sede {
         }
     }
 
-    pub fn deserialize_rc_box_any_map<'de, D>(
-        deserializer: D,
-    ) -> Result<HashMap<Path, Rc<Box<dyn Any>>>, D::Error>
+    pub fn deserialize_rc_box_any_map<'de, D>(deserializer: D) -> Result<ExportMap, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -311,6 +402,7 @@ mod sede {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::{Point2D, Vector3D};
     use crate::path;
     use crate::path::Path;
     use crate::slot::Slot::{Nbr, Rep};
@@ -461,4 +553,66 @@ mod tests {
         let export_des: Export = serde_json::from_str(&export_ser).unwrap();
         assert_eq!(export, export_des);
     }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    impl Display for Point {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{};{}", self.x, self.y)
+        }
+    }
+
+    impl FromStr for Point {
+        type Err = String;
+
+        fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+            let (x, y) = s.split_once(';').ok_or("Missing separator")?;
+            Ok(Point {
+                x: x.parse::<i32>().map_err(|e| e.to_string())?,
+                y: y.parse::<i32>().map_err(|e| e.to_string())?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_serialize_custom_type() {
+        let point = Point { x: 1, y: -2 };
+        let mut export: Export = Export::new();
+        export.put_serializable(Path::new(), point.clone());
+        export.put(path!(Nbr(0)), 10);
+        assert_eq!(export.serialized(&Path::new()), Some("1;-2".to_string()));
+
+        let export_ser = serde_json::to_string(&export).unwrap();
+        let export_des: Export = serde_json::from_str(&export_ser).unwrap();
+        assert_eq!(export_des.root::<Point>(), point);
+        assert_eq!(export, export_des);
+        assert_eq!(export_des, export);
+    }
+
+    #[test]
+    fn test_serialize_unsupported_type() {
+        let mut export: Export = Export::new();
+        export.put(Path::new(), Point { x: 1, y: 2 });
+        assert!(export.serialized(&Path::new()).is_none());
+        assert!(serde_json::to_string(&export).is_err());
+    }
+
+    #[test]
+    fn test_serialize_geometry() {
+        let mut export: Export = Export::new();
+        export.put(Path::new(), Point2D::new(1.0, 2.5));
+        export.put(path!(Nbr(0)), Vector3D::new(0.0, -1.0, 3.0));
+        let export_ser = serde_json::to_string(&export).unwrap();
+        let export_des: Export = serde_json::from_str(&export_ser).unwrap();
+        assert_eq!(export_des.root::<Point2D>(), Point2D::new(1.0, 2.5));
+        assert_eq!(
+            export_des.get::<Vector3D>(&path!(Nbr(0))).unwrap(),
+            Vector3D::new(0.0, -1.0, 3.0)
+        );
+        assert_eq!(export, export_des);
+    }
 }
@@ -0,0 +1,363 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// Operations shared by the vectors of any dimension.
+pub trait Vector:
+    Copy
+    + Default
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+    + Mul<f64, Output = Self>
+    + Div<f64, Output = Self>
+{
+    /// Computes the dot product with another vector.
+    fn dot(&self, other: &Self) -> f64;
+
+    /// The null vector.
+    fn zero() -> Self {
+        Self::default()
+    }
+
+    /// The length of the vector.
+    fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Obtains the vector with the same direction and unit length, or the null vector if this
+    /// vector is null.
+    fn normalized(&self) -> Self {
+        let norm = self.norm();
+        if norm > 0.0 {
+            *self / norm
+        } else {
+            Self::zero()
+        }
+    }
+
+    /// Obtains the vector with the same direction and a length of at most `max`.
+    fn limit(&self, max: f64) -> Self {
+        if self.norm() > max {
+            self.normalized() * max
+        } else {
+            *self
+        }
+    }
+}
+
+/// A vector in the plane.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector2D {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A vector in the space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// A point in the plane.
+///
+/// Points can be added together and divided by a scalar component-wise, so that `sum_hood` and
+/// `mean_hood` compute the centroid of the points of the neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point2D {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A point in the space.
+///
+/// Points can be added together and divided by a scalar component-wise, so that `sum_hood` and
+/// `mean_hood` compute the centroid of the points of the neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point3D {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector2D {
+    /// Creates a new vector with the given components.
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+impl Vector3D {
+    /// Creates a new vector with the given components.
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Computes the cross product with another vector.
+    pub fn cross(&self, other: &Vector3D) -> Vector3D {
+        Vector3D::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+}
+
+impl Point2D {
+    /// Creates a new point with the given coordinates.
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Computes the euclidean distance from another point.
+    pub fn distance(&self, other: &Point2D) -> f64 {
+        (*other - *self).norm()
+    }
+}
+
+impl Point3D {
+    /// Creates a new point with the given coordinates.
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Computes the euclidean distance from another point.
+    pub fn distance(&self, other: &Point3D) -> f64 {
+        (*other - *self).norm()
+    }
+}
+
+impl Vector for Vector2D {
+    fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+}
+
+impl Vector for Vector3D {
+    fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+}
+
+/// Implements the operations of [Vector] as inherent methods of a type, so that they can be called
+/// without importing the trait.
+macro_rules! inherent_vector {
+    ($t:ident) => {
+        impl $t {
+            /// The null vector.
+            pub fn zero() -> Self {
+                <Self as Vector>::zero()
+            }
+
+            /// Computes the dot product with another vector.
+            pub fn dot(&self, other: &$t) -> f64 {
+                Vector::dot(self, other)
+            }
+
+            /// The length of the vector.
+            pub fn norm(&self) -> f64 {
+                Vector::norm(self)
+            }
+
+            /// Obtains the vector with the same direction and unit length, or the null vector if
+            /// this vector is null.
+            pub fn normalized(&self) -> Self {
+                Vector::normalized(self)
+            }
+
+            /// Obtains the vector with the same direction and a length of at most `max`.
+            pub fn limit(&self, max: f64) -> Self {
+                Vector::limit(self, max)
+            }
+        }
+    };
+}
+
+inherent_vector!(Vector2D);
+inherent_vector!(Vector3D);
+
+/// Implements the component-wise operations of a type: addition of `$rhs`, subtraction of `$sub`
+/// giving `$diff`, and scaling. Also implements [Display] and [FromStr], separating the components
+/// by `;`.
+macro_rules! component_wise {
+    ($t:ident, $rhs:ident, $sub:ident, $diff:ident, $($c:ident),+) => {
+        impl Add<$rhs> for $t {
+            type Output = $t;
+
+            fn add(self, rhs: $rhs) -> Self::Output {
+                $t { $($c: self.$c + rhs.$c),+ }
+            }
+        }
+
+        impl Sub<$sub> for $t {
+            type Output = $diff;
+
+            fn sub(self, rhs: $sub) -> Self::Output {
+                $diff { $($c: self.$c - rhs.$c),+ }
+            }
+        }
+
+        impl Mul<f64> for $t {
+            type Output = $t;
+
+            fn mul(self, rhs: f64) -> Self::Output {
+                $t { $($c: self.$c * rhs),+ }
+            }
+        }
+
+        impl Div<f64> for $t {
+            type Output = $t;
+
+            fn div(self, rhs: f64) -> Self::Output {
+                $t { $($c: self.$c / rhs),+ }
+            }
+        }
+
+        impl Display for $t {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                let components = [$(self.$c.to_string()),+];
+                write!(f, "{}", components.join(";"))
+            }
+        }
+
+        impl FromStr for $t {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut components = s.split(';');
+                let value = $t {
+                    $($c: components
+                        .next()
+                        .ok_or("Missing component")?
+                        .parse::<f64>()
+                        .map_err(|e| e.to_string())?),+
+                };
+                match components.next() {
+                    Some(_) => Err("Too many components".to_string()),
+                    None => Ok(value),
+                }
+            }
+        }
+    };
+}
+
+component_wise!(Vector2D, Vector2D, Vector2D, Vector2D, x, y);
+component_wise!(Vector3D, Vector3D, Vector3D, Vector3D, x, y, z);
+component_wise!(Point2D, Vector2D, Point2D, Vector2D, x, y);
+component_wise!(Point3D, Vector3D, Point3D, Vector3D, x, y, z);
+
+impl Neg for Vector2D {
+    type Output = Vector2D;
+
+    fn neg(self) -> Self::Output {
+        Vector2D::new(-self.x, -self.y)
+    }
+}
+
+impl Neg for Vector3D {
+    type Output = Vector3D;
+
+    fn neg(self) -> Self::Output {
+        Vector3D::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Add for Point2D {
+    type Output = Point2D;
+
+    fn add(self, rhs: Point2D) -> Self::Output {
+        Point2D::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Add for Point3D {
+    type Output = Point3D;
+
+    fn add(self, rhs: Point3D) -> Self::Output {
+        Point3D::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub<Vector2D> for Point2D {
+    type Output = Point2D;
+
+    fn sub(self, rhs: Vector2D) -> Self::Output {
+        Point2D::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Sub<Vector3D> for Point3D {
+    type Output = Point3D;
+
+    fn sub(self, rhs: Vector3D) -> Self::Output {
+        Point3D::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_arithmetic() {
+        let a = Vector2D::new(1.0, 2.0);
+        let b = Vector2D::new(3.0, -1.0);
+        assert_eq!(a + b, Vector2D::new(4.0, 1.0));
+        assert_eq!(a - b, Vector2D::new(-2.0, 3.0));
+        assert_eq!(-a, Vector2D::new(-1.0, -2.0));
+        assert_eq!(a * 2.0, Vector2D::new(2.0, 4.0));
+        assert_eq!(b / 2.0, Vector2D::new(1.5, -0.5));
+        assert_eq!(a.dot(&b), 1.0);
+
+        let x = Vector3D::new(1.0, 0.0, 0.0);
+        let y = Vector3D::new(0.0, 1.0, 0.0);
+        assert_eq!(x.cross(&y), Vector3D::new(0.0, 0.0, 1.0));
+        assert_eq!(x.dot(&y), 0.0);
+        assert_eq!((x + y) * 2.0 - x, Vector3D::new(1.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn test_norm() {
+        let v = Vector2D::new(3.0, 4.0);
+        assert_eq!(v.norm(), 5.0);
+        assert_eq!(v.normalized(), Vector2D::new(0.6, 0.8));
+        assert_eq!(v.limit(10.0), v);
+        assert_eq!(v.limit(1.0), Vector2D::new(0.6, 0.8));
+        assert_eq!(Vector2D::zero().normalized(), Vector2D::zero());
+        let w = Vector3D::new(2.0, 3.0, 6.0);
+        assert_eq!(w.norm(), 7.0);
+        assert_eq!(w.limit(1.0), w / 7.0);
+        assert_eq!(Vector3D::zero().normalized(), Vector3D::zero());
+    }
+
+    #[test]
+    fn test_point_arithmetic() {
+        let p = Point2D::new(1.0, 1.0);
+        let q = Point2D::new(4.0, 5.0);
+        assert_eq!(q - p, Vector2D::new(3.0, 4.0));
+        assert_eq!(p + Vector2D::new(3.0, 4.0), q);
+        assert_eq!(q - Vector2D::new(3.0, 4.0), p);
+        assert_eq!(p.distance(&q), 5.0);
+        assert_eq!((p + q) / 2.0, Point2D::new(2.5, 3.0));
+
+        let p = Point3D::new(1.0, 2.0, 3.0);
+        let q = Point3D::new(3.0, 5.0, 9.0);
+        assert_eq!(q - p, Vector3D::new(2.0, 3.0, 6.0));
+        assert_eq!(p.distance(&q), 7.0);
+        assert_eq!(p + Vector3D::new(2.0, 3.0, 6.0), q);
+    }
+
+    #[test]
+    fn test_parse() {
+        let v = Vector2D::new(-1.5, 0.25);
+        assert_eq!(v.to_string().parse::<Vector2D>().unwrap(), v);
+        let p = Point3D::new(1.0, -2.0, 0.5);
+        assert_eq!(p.to_string(), "1;-2;0.5");
+        assert_eq!(p.to_string().parse::<Point3D>().unwrap(), p);
+        assert!("1.0".parse::<Vector2D>().is_err());
+        assert!("1;2;3".parse::<Point2D>().is_err());
+        assert!("1;a;3".parse::<Vector3D>().is_err());
+    }
+}
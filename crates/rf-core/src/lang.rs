@@ -1,5 +1,6 @@
-use crate::slot::Slot::{Branch, FoldHood, Nbr, Rep};
+use crate::slot::Slot::{Branch, FoldHood, Nbr, Rep, Scope};
 use crate::vm::round_vm::RoundVM;
+use std::fmt::Display;
 use std::str::FromStr;
 
 pub mod builtins;
@@ -15,13 +16,13 @@ pub mod macros;
 ///
 /// # Generic Parameters
 ///
-/// * `A` The type of value returned by the expression.
+/// * `A` The type of value returned by the expression. It must implement [Display] and [FromStr] to be exchanged with the neighbours.
 /// * `F` - The type of the closure, which must be a closure that takes a `RoundVM` as argument and returns a tuple `(RoundVM, A)`.
 ///
 /// # Returns
 ///
 /// the value of the expression
-pub fn nbr<A: Clone + 'static + FromStr, F>(vm: &mut RoundVM, expr: F) -> A
+pub fn nbr<A: Clone + 'static + FromStr + Display, F>(vm: &mut RoundVM, expr: F) -> A
 where
     F: Fn(&mut RoundVM) -> A,
 {
@@ -49,14 +50,14 @@ where
 ///
 /// # Generic Parameters
 ///
-/// * `A` The type of value returned by the expression.
+/// * `A` The type of value returned by the expression. It must implement [Display] and [FromStr] to be exchanged with the neighbours.
 /// * `F` - The type of the closure, which must be a closure that takes no arguments and returns a value of type `A`.
 /// * `G` - The type of the closure, which must be a closure that takes a tuple `(RoundVM, A)` and returns a tuple `(RoundVM, A)`.
 ///
 /// # Returns
 ///
 /// the updated value
-pub fn rep<A: Clone + 'static + FromStr, F, G>(vm: &mut RoundVM, init: F, fun: G) -> A
+pub fn rep<A: Clone + 'static + FromStr + Display, F, G>(vm: &mut RoundVM, init: F, fun: G) -> A
 where
     F: Fn(&mut RoundVM) -> A,
     G: Fn(&mut RoundVM, A) -> A,
@@ -83,7 +84,7 @@ where
 ///
 /// # Generic Parameters
 ///
-/// * `A` The type of value returned by the expression.
+/// * `A` The type of value returned by the expression. It must implement [Display] and [FromStr] to be exchanged with the neighbours.
 /// * `F` - The type of inti, which must be a closure that takes no arguments and returns a value of type `A`.
 /// * `G` - The type of aggr, which must be a closure that takes a tuple `(A, A)` and returns a value of type `A`.
 /// * `H` - The type of expr, which must be a closure that takes a `RoundVM` as argument and returns a tuple `(RoundVM, A)`.
@@ -91,7 +92,7 @@ where
 /// # Returns
 ///
 /// the aggregated value
-pub fn foldhood<A: Clone + 'static + FromStr, F, G, H>(
+pub fn foldhood<A: Clone + 'static + FromStr + Display, F, G, H>(
     vm: &mut RoundVM,
     init: F,
     aggr: G,
@@ -124,6 +125,10 @@ where
 
 /// Partitions the domain into two subspaces that do not interact with each other.
 ///
+/// Since version 0.7.0 the evaluated expression is nested under a `Scope` holding the value of the
+/// condition, so that the two subspaces are kept apart like the keys of [align]. This changes the
+/// paths of the values in the exports, thus devices running older versions do not align with it.
+///
 /// # Arguments
 ///
 /// * `vm` the current VM
@@ -133,14 +138,14 @@ where
 ///
 /// # Generic Parameters
 ///
-/// * `A` The type of value returned by the expression.
+/// * `A` The type of value returned by the expression. It must implement [Display] and [FromStr] to be exchanged with the neighbours.
 /// * `B` - The type of cond, which must be a closure that takes no arguments and returns a value of type `bool`.
 /// * `F` - The type of thn and els, which must be a closure that takes a `RoundVM` as argument and returns a tuple `(RoundVM, A)`.
 ///
 /// # Returns
 ///
 /// the value of the expression
-pub fn branch<A: Clone + 'static + FromStr, B, TH, EL>(
+pub fn branch<A: Clone + 'static + FromStr + Display, B, TH, EL>(
     vm: &mut RoundVM,
     cond: B,
     thn: TH,
@@ -159,19 +164,53 @@ where
             let tag = vm.locally(|_vm1| cond());
             let val: A = match vm.neighbor() {
                 Some(nbr) if nbr != vm.self_id() => vm.neighbor_val::<A>().unwrap(),
-                _ => {
-                    if tag {
-                        vm.locally(thn)
-                    } else {
-                        vm.locally(els)
-                    }
-                }
+                _ => vm.nest(
+                    Scope(tag as i32),
+                    vm.unless_folding_on_others(),
+                    false,
+                    |vm1| {
+                        if tag {
+                            vm1.locally(thn)
+                        } else {
+                            vm1.locally(els)
+                        }
+                    },
+                ),
             };
             val
         },
     )
 }
 
+/// Evaluates an expression aligning only with the neighbours that evaluate it with the same key,
+/// which partitions the domain into as many subspaces as the keys.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `key` the key of the subspace of the current device
+/// * `expr` the expression to evaluate
+///
+/// # Generic Parameters
+///
+/// * `A` The type of value returned by the expression. It must implement [Display] and [FromStr] to be exchanged with the neighbours.
+/// * `F` - The type of expr, which must be a closure that takes a `RoundVM` as argument and returns a value of type `A`.
+///
+/// # Returns
+///
+/// the value of the expression
+pub fn align<A: Clone + 'static + FromStr + Display, F>(vm: &mut RoundVM, key: i32, expr: F) -> A
+where
+    F: Fn(&mut RoundVM) -> A,
+{
+    vm.nest(
+        Branch(vm.index()),
+        vm.unless_folding_on_others(),
+        true,
+        |vm| vm.nest(Scope(key), vm.unless_folding_on_others(), false, &expr),
+    )
+}
+
 /// Returns the id of the current device.
 ///
 /// # Arguments
@@ -1,5 +1,13 @@
-use crate::lang::{foldhood, mid, nbr};
+use crate::geometry::Vector2D;
+use crate::lang::{foldhood, mid, nbr, rep};
+use crate::sensor_id::sensor;
+use crate::slot::Slot::Scope;
 use crate::vm::round_vm::RoundVM;
+use rand::Rng;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div};
 use std::str::FromStr;
 
 /// Evaluates the given expressions and returns the result based on the given condition.
@@ -48,7 +56,7 @@ where
 /// # Returns
 ///
 /// the aggregated value
-pub fn foldhood_plus<A: Copy + 'static + FromStr, F, G, H>(
+pub fn foldhood_plus<A: Clone + 'static + FromStr + Display, F, G, H>(
     vm: &mut RoundVM,
     init: F,
     aggr: G,
@@ -65,3 +73,289 @@ where
         mux(vm1, |_vm2| self_id == nbr_id, init, expr)
     })
 }
+
+/// Sums the values of the given expression over the aligned neighbors, self included.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `expr` the expression to evaluate
+///
+/// # Returns
+///
+/// the sum of the values, starting from the default value of `A`
+pub fn sum_hood<A, H>(vm: &mut RoundVM, expr: H) -> A
+where
+    A: Clone + 'static + FromStr + Display + Default + Add<Output = A>,
+    H: Fn(&mut RoundVM) -> A + Copy,
+{
+    foldhood(vm, |_vm| A::default(), |a, b| a + b, expr)
+}
+
+/// Sums the values of the given expression over the aligned neighbors, excluding self.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `expr` the expression to evaluate
+///
+/// # Returns
+///
+/// the sum of the values, starting from the default value of `A`
+pub fn sum_hood_plus<A, H>(vm: &mut RoundVM, expr: H) -> A
+where
+    A: Clone + 'static + FromStr + Display + Default + Add<Output = A>,
+    H: Fn(&mut RoundVM) -> A + Copy,
+{
+    foldhood_plus(vm, |_vm| A::default(), |a, b| a + b, expr)
+}
+
+/// Averages the values of the given expression over the aligned neighbors, self included.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `expr` the expression to evaluate
+///
+/// # Returns
+///
+/// the mean of the values
+pub fn mean_hood<A, H>(vm: &mut RoundVM, expr: H) -> A
+where
+    A: Clone + 'static + FromStr + Display + Default + Add<Output = A> + Div<f64, Output = A>,
+    H: Fn(&mut RoundVM) -> A + Copy,
+{
+    foldhood(
+        vm,
+        |_vm| Mean::default(),
+        Mean::merge,
+        |vm1| Mean::of(expr(vm1)),
+    )
+    .value()
+}
+
+/// Averages the values of the given expression over the aligned neighbors, excluding self.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `expr` the expression to evaluate
+///
+/// # Returns
+///
+/// the mean of the values, or the default value of `A` if there are no neighbors
+pub fn mean_hood_plus<A, H>(vm: &mut RoundVM, expr: H) -> A
+where
+    A: Clone + 'static + FromStr + Display + Default + Add<Output = A> + Div<f64, Output = A>,
+    H: Fn(&mut RoundVM) -> A + Copy,
+{
+    foldhood_plus(
+        vm,
+        |_vm| Mean::default(),
+        Mean::merge,
+        |vm1| Mean::of(expr(vm1)),
+    )
+    .value()
+}
+
+/// The partial state of an average: the number of values and their sum.
+#[derive(Debug, Clone, Default)]
+struct Mean<A> {
+    count: f64,
+    sum: A,
+}
+
+impl<A: Add<Output = A> + Div<f64, Output = A> + Default> Mean<A> {
+    fn of(value: A) -> Self {
+        Mean {
+            count: 1.0,
+            sum: value,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Mean {
+            count: self.count + other.count,
+            sum: self.sum + other.sum,
+        }
+    }
+
+    fn value(self) -> A {
+        if self.count > 0.0 {
+            self.sum / self.count
+        } else {
+            A::default()
+        }
+    }
+}
+
+impl<A: Display> Display for Mean<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.count, self.sum)
+    }
+}
+
+impl<A: FromStr> FromStr for Mean<A> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, sum) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Mean {
+            count: count.parse::<f64>().map_err(|e| e.to_string())?,
+            sum: sum.parse::<A>().map_err(|_| "Cannot parse sum")?,
+        })
+    }
+}
+
+/// Runs a set of aggregate processes, each identified by a key. A process is started by the
+/// devices generating its key and spreads to their neighbours, running on every device until it
+/// decides to leave it. Each process is evaluated in isolation from the others, and devices align
+/// only with the neighbours running the same process.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+/// * `process` the process to run, which takes the key and returns its value together with
+///   whether the current device keeps running it
+/// * `new_keys` the keys of the processes generated by the current device in this round
+///
+/// # Generic Parameters
+///
+/// * `A` The type of value returned by the processes.
+/// * `F` - The type of process, which must be a closure that takes a `RoundVM` and a key as arguments and returns a tuple `(A, bool)`.
+///
+/// # Returns
+///
+/// the values of the processes the current device keeps running, by key
+pub fn spawn<A, F>(vm: &mut RoundVM, process: F, new_keys: &[i32]) -> HashMap<i32, A>
+where
+    A: Clone + 'static + FromStr + Display,
+    F: Fn(&mut RoundVM, i32) -> (A, bool) + Copy,
+{
+    let outputs = RefCell::new(HashMap::new());
+    rep(
+        vm,
+        |_vm| Ids(vec![]),
+        |vm1, running| {
+            let nbr_keys = foldhood(
+                vm1,
+                |_vm| Ids(vec![]),
+                |mut a, b| {
+                    a.0.extend(b.0);
+                    a
+                },
+                |vm2| nbr(vm2, |_vm| running.clone()),
+            );
+            let mut keys: Vec<i32> = new_keys.iter().cloned().chain(nbr_keys.0).collect();
+            keys.sort();
+            keys.dedup();
+            // Every process is nested at the same index, so that it is aligned whatever the other
+            // processes run by each device
+            let alive = keys
+                .into_iter()
+                .filter(|key| {
+                    let keep = Cell::new(false);
+                    let value =
+                        vm1.nest(Scope(*key), vm1.unless_folding_on_others(), false, |vm2| {
+                            let (value, alive) = process(vm2, *key);
+                            keep.set(alive);
+                            value
+                        });
+                    if keep.get() {
+                        outputs.borrow_mut().insert(*key, value);
+                    }
+                    keep.get()
+                })
+                .collect();
+            Ids(alive)
+        },
+    );
+    outputs.into_inner()
+}
+
+/// A list of integers, such as device ids or the keys of the processes run by [spawn], separated
+/// by commas so that it can be used as a field value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ids(pub Vec<i32>);
+
+impl Display for Ids {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ids: Vec<String> = self.0.iter().map(|id| id.to_string()).collect();
+        write!(f, "{}", ids.join(","))
+    }
+}
+
+impl FromStr for Ids {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse::<i32>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<i32>, String>>()
+            .map(Ids)
+    }
+}
+
+/// Returns a random number uniformly distributed in the range [0, 1), drawn from the random number
+/// generator of the current device. Since the VM is created anew at each round, a device seeded
+/// through the "seed" local sensor draws the same sequence in every round.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+///
+/// # Returns
+///
+/// the random number
+pub fn next_random(vm: &mut RoundVM) -> f64 {
+    vm.rng().gen::<f64>()
+}
+
+/// Returns the time elapsed, in seconds, since the previous round of the current device, as
+/// perceived by the "delta_time" local sensor.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+///
+/// # Returns
+///
+/// the time elapsed since the previous round, or 0 if it is unknown
+pub fn delta_time(vm: &mut RoundVM) -> f64 {
+    vm.local_sense::<f64>(&sensor("delta_time"))
+        .cloned()
+        .unwrap_or(0.0)
+}
+
+/// Returns the time elapsed, in seconds, since the neighbour currently being folded produced its
+/// export, as perceived by the "nbr_lag" neighbouring sensor.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+///
+/// # Returns
+///
+/// the lag of the neighbour, or 0 if it is unknown
+pub fn nbr_lag(vm: &mut RoundVM) -> f64 {
+    vm.nbr_sense::<f64>(&sensor("nbr_lag"))
+        .cloned()
+        .unwrap_or(0.0)
+}
+
+/// Returns the position of the neighbour currently being folded relative to the current device, as
+/// perceived by the "nbr_vector" neighbouring sensor.
+///
+/// # Arguments
+///
+/// * `vm` the current VM
+///
+/// # Returns
+///
+/// the vector from the current device to the neighbour, or the null vector if it is unknown
+pub fn nbr_vector(vm: &mut RoundVM) -> Vector2D {
+    vm.nbr_sense::<Vector2D>(&sensor("nbr_vector"))
+        .cloned()
+        .unwrap_or_default()
+}
@@ -1,7 +1,8 @@
 use crate::vm::round_vm::RoundVM;
+use std::fmt::Display;
 use std::str::FromStr;
 
-pub fn round<A: Clone + 'static + FromStr>(
+pub fn round<A: Clone + 'static + FromStr + Display>(
     vm: &mut RoundVM,
     program: impl Fn(&mut RoundVM) -> A,
 ) -> A {
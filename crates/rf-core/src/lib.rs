@@ -1,5 +1,6 @@
 pub mod context;
 pub mod export;
+pub mod geometry;
 pub mod lang;
 pub mod path;
 pub mod sensor_id;
@@ -6,6 +6,7 @@ use std::fmt::{Display, Formatter};
 /// * `Nbr(index)` - The value of an expression across neighbours.
 /// * `Rep(index)` - It iteratively updates the value of the input expression at each device using the last computed value.
 /// * `Branch(index)` - Partition the domain into two subspaces that do not interact with each other.
+/// * `Scope(key)` - Restrict the interaction to the devices evaluating the same key, nested in a `Branch`.
 /// * `Exchange(index)` - The exchange construct handles neighbour-to-neighbour propagation of partial accumulates.
 #[derive(PartialEq, Debug, Clone, Copy, Eq, Hash, Serialize, Deserialize)]
 pub enum Slot {
@@ -13,6 +14,7 @@ pub enum Slot {
     Rep(i32),
     FoldHood(i32),
     Branch(i32),
+    Scope(i32),
     Exchange(i32),
 }
 
@@ -23,6 +25,7 @@ impl Display for Slot {
             Slot::Rep(index) => write!(f, "Rep({})", index),
             Slot::FoldHood(index) => write!(f, "FoldHood({})", index),
             Slot::Branch(index) => write!(f, "Branch({})", index),
+            Slot::Scope(key) => write!(f, "Scope({})", key),
             Slot::Exchange(index) => write!(f, "Exchange({})", index),
         }
     }
@@ -51,10 +54,12 @@ mod test {
         let nbr = Slot::Nbr(0);
         let rep = Slot::Rep(0);
         let branch = Slot::Branch(0);
+        let scope = Slot::Scope(3);
         let exchange = Slot::Exchange(0);
         assert_eq!(nbr.to_string(), "Nbr(0)");
         assert_eq!(rep.to_string(), "Rep(0)");
         assert_eq!(branch.to_string(), "Branch(0)");
+        assert_eq!(scope.to_string(), "Scope(3)");
         assert_eq!(exchange.to_string(), "Exchange(0)");
     }
 
@@ -1,9 +1,12 @@
 use crate::context::Context;
 use crate::export::{Export, Result};
 use crate::path::Path;
-use crate::sensor_id::SensorId;
+use crate::sensor_id::{sensor, SensorId};
 use crate::slot::Slot;
 use crate::vm::vm_status::VMStatus;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt::Display;
 use std::str::FromStr;
 
 /// A Round correspond to a local computation in a device. Create the context, evaluate the aggregate program and share the exports to the neighborhood.
@@ -13,17 +16,28 @@ use std::str::FromStr;
 /// * `status` - The status of the current round.
 ///
 /// * `export_stack` - The stack of exports of the current round.
+///
+/// * `rng` - The random number generator of the device.
 #[derive(Debug, Clone)]
 pub struct RoundVM {
     context: Context,
     status: VMStatus,
     export_stack: Vec<Export>,
     isolated: bool,
+    rng: StdRng,
 }
 
 impl RoundVM {
     /// Create a new RoundVM
     ///
+    /// If the "seed" local sensor holds a `u64`, the random number generator of the device is
+    /// derived from it and from the id of the device, so that the same seed always produces the
+    /// same numbers. Otherwise, it is seeded from the entropy of the system.
+    ///
+    /// Since a new VM is created at each round, so is the generator: a seeded device draws the same
+    /// numbers in every round, and values meant to change over time must be drawn from a different
+    /// seed at each round or stored with [rep](crate::lang::rep).
+    ///
     /// ### Arguments
     ///
     /// * `context` - The context of the current round.
@@ -32,11 +46,18 @@ impl RoundVM {
     ///
     /// A `RoundVM` instance.
     pub fn new(context: Context) -> Self {
+        let rng = match context.local_sense::<u64>(&sensor("seed")) {
+            Some(seed) => StdRng::seed_from_u64(
+                seed ^ (*context.self_id() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+            ),
+            None => StdRng::from_entropy(),
+        };
         Self {
             context,
             status: VMStatus::new(),
             export_stack: vec![],
             isolated: false,
+            rng,
         }
     }
 
@@ -64,10 +85,10 @@ impl RoundVM {
     ///
     /// # Generic Parameters
     ///
-    /// * `A` - The type of value. It must implement the `Copy` trait
-    ///         and have a `'static` lifetime.
-    pub fn register_root<A: 'static + Clone>(&mut self, v: A) {
-        self.export_data().put(Path::new(), v.clone());
+    /// * `A` - The type of value. It must implement the `Clone` and `Display` traits
+    ///   and have a `'static` lifetime.
+    pub fn register_root<A: 'static + Clone + Display>(&mut self, v: A) {
+        self.export_data().put_serializable(Path::new(), v.clone());
     }
 
     /// If the computation is folding on a neighbor, return the id of the neighbor
@@ -91,7 +112,7 @@ impl RoundVM {
     /// # Generic Parameters
     ///
     /// * `A` - The type of value. It must implement the `Clone` trait
-    ///         and have a `'static` lifetime.
+    ///   and have a `'static` lifetime.
     ///
     /// # Returns
     ///
@@ -106,7 +127,7 @@ impl RoundVM {
     /// # Generic Parameters
     ///
     /// * `A` - The type of value. It must implement the `Clone` trait
-    ///         and have a `'static` lifetime.
+    ///   and have a `'static` lifetime.
     ///
     /// # Returns
     ///
@@ -214,13 +235,13 @@ impl RoundVM {
     ///
     /// # Generic Parameters
     ///
-    /// * `A` - The type of value returned by the expression.
+    /// * `A` - The type of value returned by the expression. It must implement [Display] and [FromStr] to be serialized in the [Export].
     /// * `F` - The type of the expression, which must be a closure that takes a [RoundVM] as argument and returns a tuple of `RoundVM` and `A`.
     ///
     /// # Returns
     ///
     /// A tuple of `RoundVM` and `A`.
-    pub fn nest<A: Clone + 'static + FromStr, F>(
+    pub fn nest<A: Clone + 'static + FromStr + Display, F>(
         &mut self,
         slot: Slot,
         write: bool,
@@ -333,6 +354,14 @@ impl RoundVM {
         &self.context
     }
 
+    /// # Returns
+    ///
+    /// The random number generator of the device, of type `&mut StdRng`. It only lives for the
+    /// current round: see [RoundVM::new] for how it is seeded.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
     /// Create a new export stack with an empty [Export]. This function needs to be called when a new
     /// [RoundVM] is created.
     pub fn new_export_stack(&mut self) {
@@ -341,6 +370,7 @@ impl RoundVM {
 }
 
 #[cfg(test)]
+#[allow(unused_variables, clippy::clone_on_copy)]
 mod tests {
     use crate::context::Context;
     use crate::export;
@@ -351,6 +381,7 @@ mod tests {
     use crate::slot::Slot::{Nbr, Rep};
     use crate::vm::round_vm::RoundVM;
     use crate::vm::vm_status::VMStatus;
+    use rand::Rng;
     use std::any::Any;
     use std::collections::HashMap;
     use std::rc::Rc;
@@ -460,6 +491,19 @@ mod tests {
         assert!(vm.unless_folding_on_others());
     }
 
+    #[test]
+    fn test_seeded_rng() {
+        let seeded = |id: i32, seed: u64| {
+            let local_sensor =
+                HashMap::from([(sensor("seed"), Rc::new(Box::new(seed) as Box<dyn Any>))]);
+            let context = Context::new(id, local_sensor, HashMap::new(), HashMap::new());
+            RoundVM::new(context).rng().gen::<u64>()
+        };
+        assert_eq!(seeded(1, 42), seeded(1, 42));
+        assert_ne!(seeded(1, 42), seeded(2, 42));
+        assert_ne!(seeded(1, 42), seeded(1, 43));
+    }
+
     #[test]
     fn test_only_when_folding_on_self() {
         let mut vm = round_vm_builder();
@@ -55,6 +55,7 @@ impl VMStatus {
     /// # Returns
     ///
     /// The stack of statuses.
+    #[allow(dead_code)]
     pub fn stack(&self) -> &VecDeque<(Path, i32, Option<i32>)> {
         &self.stack
     }
@@ -64,6 +65,7 @@ impl VMStatus {
     /// # Returns
     ///
     /// True if the VM is folding, false otherwise.
+    #[allow(dead_code)]
     pub fn is_folding(&self) -> bool {
         self.neighbour.is_some()
     }
@@ -1,11 +1,13 @@
 use crate::utils::{combine, init_vm, init_with_ctx, push_to_ctx};
 use rf_core::context::Context;
 use rf_core::export::Export;
+use rf_core::geometry::Point2D;
+use rf_core::lang::builtins::{mean_hood, spawn, sum_hood};
 use rf_core::lang::execution::round;
-use rf_core::lang::{branch, foldhood, mid, nbr, rep};
+use rf_core::lang::{align, branch, foldhood, mid, nbr, rep};
 use rf_core::path::Path;
 use rf_core::sensor_id::sensor;
-use rf_core::slot::Slot::{FoldHood, Nbr, Rep};
+use rf_core::slot::Slot::{Branch, FoldHood, Nbr, Rep, Scope};
 use rf_core::vm::round_vm::RoundVM;
 use rf_core::{export, path};
 use std::any::Any;
@@ -192,6 +194,59 @@ fn test_foldhood_advanced() {
     assert_eq!(20, result);
 }
 
+#[test]
+fn test_hood_operators() {
+    // Exports of devices 2 and 4, whose neighbouring values are respectively 4.0 and 6.0, with the
+    // given values at the root of the foldhood
+    fn ctx<A: Clone + 'static>(fold_dev_2: A, fold_dev_4: A) -> Context {
+        let export_dev_2 = export!(
+            (path!(), fold_dev_2.clone()),
+            (path!(FoldHood(0)), fold_dev_2.clone()),
+            (path!(Nbr(0), FoldHood(0)), 4.0)
+        );
+        let export_dev_4 = export!(
+            (path!(), fold_dev_4.clone()),
+            (path!(FoldHood(0)), fold_dev_4.clone()),
+            (path!(Nbr(0), FoldHood(0)), 6.0)
+        );
+        let exports = HashMap::from([(2, export_dev_2), (4, export_dev_4)]);
+        Context::new(0, Default::default(), Default::default(), exports)
+    }
+
+    // Program: sumHood(nbr(2.0))
+    let program = |vm: &mut RoundVM| sum_hood(vm, |vm1| nbr(vm1, |_vm| 2.0));
+    let result = round(&mut init_with_ctx(ctx(4.0, 6.0)), program);
+    assert_eq!(12.0, result);
+
+    // Program: meanHood(nbr(2.0)), where each neighbour exports the number of values and their sum
+    let program = |vm: &mut RoundVM| mean_hood(vm, |vm1| nbr(vm1, |_vm| 2.0));
+    let context = ctx(String::from("1;4"), String::from("1;6"));
+    let result = round(&mut init_with_ctx(context), program);
+    assert_eq!(4.0, result);
+}
+
+#[test]
+fn test_hood_operators_on_geometry() {
+    // Exports of devices 2 and 4, which are respectively in (2, 0) and (4, 6)
+    let export_dev_2 = export!(
+        (path!(), String::from("1;2;0")),
+        (path!(FoldHood(0)), String::from("1;2;0")),
+        (path!(Nbr(0), FoldHood(0)), Point2D::new(2.0, 0.0))
+    );
+    let export_dev_4 = export!(
+        (path!(), String::from("1;4;6")),
+        (path!(FoldHood(0)), String::from("1;4;6")),
+        (path!(Nbr(0), FoldHood(0)), Point2D::new(4.0, 6.0))
+    );
+    let exports = HashMap::from([(2, export_dev_2), (4, export_dev_4)]);
+    let context = Context::new(0, Default::default(), Default::default(), exports);
+
+    // Program: meanHood(nbr(position)), with the current device in (0, 0)
+    let program = |vm: &mut RoundVM| mean_hood(vm, |vm1| nbr(vm1, |_vm| Point2D::new(0.0, 0.0)));
+    let result = round(&mut init_with_ctx(context), program);
+    assert_eq!(Point2D::new(2.0, 2.0), result);
+}
+
 #[test]
 fn test_nbr() {
     fn create_exports_nbr_test() -> HashMap<i32, Export> {
@@ -314,6 +369,97 @@ fn test_branch() {
     assert_eq!(2, result);
 }
 
+#[test]
+// Branch should not align devices evaluating different branches
+fn test_branch_isolation() {
+    // Export of device 1, which took the then branch: Export(/ -> 1, FoldHood(0) / Scope(1) / Branch(0) -> 1)
+    let export_dev_1 = export!((path!(), 1), (path!(FoldHood(0), Scope(1), Branch(0)), 1));
+    // Export of device 2, which took the else branch: Export(/ -> 1, FoldHood(0) / Scope(0) / Branch(0) -> 1)
+    let export_dev_2 = export!((path!(), 1), (path!(FoldHood(0), Scope(0), Branch(0)), 1));
+    let mut exports: HashMap<i32, Export> = HashMap::new();
+    exports.insert(1, export_dev_1);
+    exports.insert(2, export_dev_2);
+    let context = Context::new(0, Default::default(), Default::default(), exports);
+    // Program: branch(true)(foldhood(0)(_ + _)(1))(foldhood(0)(_ + _)(1))
+    let program = |vm: &mut RoundVM| {
+        branch(
+            vm,
+            || true,
+            |vm1| foldhood(vm1, |_vm| 0, |a, b| a + b, |_vm| 1),
+            |vm1| foldhood(vm1, |_vm| 0, |a, b| a + b, |_vm| 1),
+        )
+    };
+    let result = round(&mut init_with_ctx(context), program);
+    // Only device 1 is aligned, along with the device itself
+    assert_eq!(2, result);
+}
+
+#[test]
+// Align should only align devices evaluating the same key
+fn test_align() {
+    let export_with_key = |key: i32| {
+        export!(
+            (path!(), 1),
+            (path!(Scope(key), Branch(0)), 1),
+            (path!(FoldHood(0), Scope(key), Branch(0)), 1)
+        )
+    };
+    let mut exports: HashMap<i32, Export> = HashMap::new();
+    exports.insert(1, export_with_key(7));
+    exports.insert(2, export_with_key(7));
+    exports.insert(3, export_with_key(8));
+    let context = Context::new(0, Default::default(), Default::default(), exports);
+    // Program: align(7)(foldhood(0)(_ + _)(1))
+    let program =
+        |vm: &mut RoundVM| align(vm, 7, |vm1| foldhood(vm1, |_vm| 0, |a, b| a + b, |_vm| 1));
+    let result = round(&mut init_with_ctx(context.clone()), program);
+    assert_eq!(3, result);
+    // Program: align(9)(foldhood(0)(_ + _)(1))
+    let program =
+        |vm: &mut RoundVM| align(vm, 9, |vm1| foldhood(vm1, |_vm| 0, |a, b| a + b, |_vm| 1));
+    let result = round(&mut init_with_ctx(context), program);
+    assert_eq!(1, result);
+}
+
+#[test]
+fn test_spawn() {
+    // Device 0 generates process 1, while its neighbour 2 runs process 3
+    let export_dev_2 = export!(
+        (path!(), 0),
+        (path!(Rep(0)), String::from("3")),
+        (path!(FoldHood(0), Rep(0)), String::from("3")),
+        (path!(Nbr(0), FoldHood(0), Rep(0)), String::from("3"))
+    );
+    let exports = HashMap::from([(2, export_dev_2)]);
+    let context = Context::new(0, Default::default(), Default::default(), exports);
+
+    // Every process keeps running on device 0
+    let program = |vm: &mut RoundVM| {
+        let outputs = spawn(vm, |_vm, key| (key * 10, true), &[1]);
+        assert_eq!(outputs, HashMap::from([(1, 10), (3, 30)]));
+        outputs.len() as i32
+    };
+    let mut vm = init_with_ctx(context.clone());
+    assert_eq!(2, round(&mut vm, program));
+    assert_eq!(
+        Some("1,3".to_string()),
+        vm.export_data().serialized(&path!(Rep(0)))
+    );
+
+    // Device 0 leaves process 3, so it does not spread it further
+    let program = |vm: &mut RoundVM| {
+        let outputs = spawn(vm, |_vm, key| (key * 10, key != 3), &[1]);
+        assert_eq!(outputs, HashMap::from([(1, 10)]));
+        outputs.len() as i32
+    };
+    let mut vm = init_with_ctx(context);
+    assert_eq!(1, round(&mut vm, program));
+    assert_eq!(
+        Some("1".to_string()),
+        vm.export_data().serialized(&path!(Rep(0)))
+    );
+}
+
 #[test]
 fn test_sense() {
     // Sense should simply evaluate to the last value read by sensor
@@ -11,6 +11,7 @@ use rf_core::vm::round_vm::RoundVM;
 use rf_core::{export, path};
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 use std::iter;
 use std::rc::Rc;
 use std::str::FromStr;
@@ -91,7 +92,7 @@ fn add_source(topology: &mut Topology, source: i32) {
 fn run_on_device<A, F: Copy>(program: F, mut topology: Topology, d: i32) -> Topology
 where
     F: Fn(&mut RoundVM) -> A,
-    A: Clone + 'static + FromStr,
+    A: Clone + 'static + FromStr + Display,
 {
     // Setup the VM
     let curr = topology.states.get(&d).unwrap().clone();
@@ -122,7 +123,7 @@ where
 fn run_on_topology<A, F>(program: F, mut topology: Topology, scheduling: &Vec<i32>) -> Topology
 where
     F: Fn(&mut RoundVM) -> A + Copy,
-    A: Clone + 'static + FromStr,
+    A: Clone + 'static + FromStr + Display,
 {
     // For each device in the provided scheduling, run the program on the device.
     for d in scheduling {
@@ -1,3 +1,17 @@
+// The tests predate the clippy configuration of the workspace and are kept as they were written.
+#![allow(
+    dead_code,
+    unused_parens,
+    unused_variables,
+    clippy::clone_on_copy,
+    clippy::manual_repeat_n,
+    clippy::map_clone,
+    clippy::multiple_bound_locations,
+    clippy::needless_borrow,
+    clippy::redundant_closure,
+    clippy::to_string_in_format_args
+)]
+
 mod by_equivalence;
 mod by_round;
 mod gradient;
@@ -6,7 +6,7 @@ use rf_core::sensor_id::SensorId;
 use rf_core::vm::round_vm::RoundVM;
 use std::any::Any;
 use std::collections::HashMap;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::rc::Rc;
 use std::str::FromStr;
 
@@ -64,7 +64,7 @@ pub fn assert_equivalence<A, F, G>(
 where
     F: Fn(&mut RoundVM) -> A + Copy,
     G: Fn(&mut RoundVM) -> A + Copy,
-    A: Eq + Clone + 'static + Debug + FromStr,
+    A: Eq + Clone + 'static + Debug + Display + FromStr,
 {
     let states = nbrs
         .iter()
@@ -98,7 +98,7 @@ fn assert_equivalence_rec<A, F, G>(
 where
     F: Fn(&mut RoundVM) -> A + Copy,
     G: Fn(&mut RoundVM) -> A + Copy,
-    A: Eq + Clone + 'static + Debug + FromStr,
+    A: Eq + Clone + 'static + Debug + Display + FromStr,
 {
     if exec_order.is_empty() {
         return true;
@@ -1,3 +1,4 @@
 pub mod mailbox;
 pub mod network;
+pub mod scheduler;
 pub mod time;
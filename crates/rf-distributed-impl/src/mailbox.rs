@@ -1,7 +1,7 @@
 use rf_distributed::mailbox::{Mailbox, Messages};
 use rf_distributed::message::Message;
-use std::collections::{BTreeMap, HashMap};
-use std::time::SystemTime;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, SystemTime};
 
 /// This struct is used as a factory for [Mailbox]es.
 pub struct MailboxFactory;
@@ -62,6 +62,10 @@ impl Mailbox for MemoryLessMailbox {
     fn messages(&mut self) -> Messages {
         self.messages.clone()
     }
+
+    fn remove(&mut self, nbr: i32) {
+        self.messages.remove(&nbr);
+    }
 }
 
 pub struct TimeOrderedMailbox {
@@ -92,11 +96,124 @@ impl Mailbox for TimeOrderedMailbox {
         }
         messages
     }
+
+    fn remove(&mut self, nbr: i32) {
+        self.messages.remove(&nbr);
+    }
+}
+
+/// When the last message of a neighbour has been received.
+struct LastUpdate {
+    time: SystemTime,
+    round: usize,
+}
+
+/// This struct wraps a [Mailbox], discarding the messages of the neighbours that are no longer
+/// around, so that a device that crashed stops influencing the rounds of its neighbours.
+///
+/// The retention policies are combined: the messages of a neighbour are discarded as soon as any
+/// of them expires. Without policies, the messages are kept forever.
+pub struct RetainingMailbox<M: Mailbox> {
+    mailbox: M,
+    max_age: Option<Duration>,
+    max_rounds: Option<usize>,
+    drop_undiscovered: bool,
+    discovered: Option<HashSet<i32>>,
+    last_updates: HashMap<i32, LastUpdate>,
+    round: usize,
+}
+
+impl<M: Mailbox> RetainingMailbox<M> {
+    /// Wraps the given mailbox, which keeps the messages forever until a policy is added.
+    pub fn new(mailbox: M) -> Self {
+        RetainingMailbox {
+            mailbox,
+            max_age: None,
+            max_rounds: None,
+            drop_undiscovered: false,
+            discovered: None,
+            last_updates: HashMap::new(),
+            round: 0,
+        }
+    }
+
+    /// Discards the messages of a neighbour when nothing has been received from it for the
+    /// given duration.
+    pub fn expire_after(mut self, duration: Duration) -> Self {
+        self.max_age = Some(duration);
+        self
+    }
+
+    /// Discards the messages of a neighbour when nothing has been received from it for more than
+    /// the given number of rounds.
+    pub fn expire_after_rounds(mut self, rounds: usize) -> Self {
+        self.max_rounds = Some(rounds);
+        self
+    }
+
+    /// Discards the messages of the neighbours that are no longer reported by the discovery.
+    pub fn drop_undiscovered(mut self) -> Self {
+        self.drop_undiscovered = true;
+        self
+    }
+
+    fn is_expired(&self, nbr: i32, update: &LastUpdate, now: SystemTime) -> bool {
+        let age = now.duration_since(update.time).unwrap_or_default();
+        let too_old = self.max_age.is_some_and(|max_age| age > max_age);
+        let too_many_rounds = self
+            .max_rounds
+            .is_some_and(|max_rounds| self.round - update.round > max_rounds);
+        let undiscovered = self.drop_undiscovered
+            && self
+                .discovered
+                .as_ref()
+                .is_some_and(|discovered| !discovered.contains(&nbr));
+        too_old || too_many_rounds || undiscovered
+    }
+}
+
+impl<M: Mailbox> Mailbox for RetainingMailbox<M> {
+    fn enqueue(&mut self, msg: Message) {
+        let update = LastUpdate {
+            time: SystemTime::now(),
+            round: self.round,
+        };
+        self.last_updates.insert(msg.source, update);
+        self.mailbox.enqueue(msg);
+    }
+
+    fn messages(&mut self) -> Messages {
+        let now = SystemTime::now();
+        let expired: Vec<i32> = self
+            .last_updates
+            .iter()
+            .filter(|(nbr, update)| self.is_expired(**nbr, update, now))
+            .map(|(nbr, _)| *nbr)
+            .collect();
+        for nbr in expired {
+            self.remove(nbr);
+        }
+        self.round += 1;
+        let mut messages = self.mailbox.messages();
+        // The wrapped mailbox may not support removing the messages
+        messages.retain(|nbr, _| self.last_updates.contains_key(nbr));
+        messages
+    }
+
+    fn remove(&mut self, nbr: i32) {
+        self.last_updates.remove(&nbr);
+        self.mailbox.remove(nbr);
+    }
+
+    fn update_neighbours(&mut self, nbrs: &[i32]) {
+        self.discovered = Some(nbrs.iter().cloned().collect());
+        self.mailbox.update_neighbours(nbrs);
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::mailbox::MailboxFactory;
+    use crate::mailbox::{MailboxFactory, RetainingMailbox};
     use rf_core::export;
     use rf_core::export::Export;
     use rf_core::path::Path;
@@ -104,7 +221,12 @@ mod test {
     use rf_distributed::message::Message;
     use std::any::Any;
     use std::collections::HashMap;
-    use std::time::SystemTime;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    fn message(source: i32, value: i32) -> Message {
+        Message::new(source, export!((Path::new(), value)), SystemTime::now())
+    }
 
     #[test]
     fn test_memory_less() {
@@ -183,4 +305,62 @@ mod test {
         let messages = mailbox.messages();
         assert_eq!(messages, HashMap::from([(2, new_msg_2), (3, new_msg_3)]));
     }
+
+    #[test]
+    fn test_expire_after() {
+        let mut mailbox = RetainingMailbox::new(MailboxFactory::memory_less())
+            .expire_after(Duration::from_millis(50));
+        let msg_2 = message(2, 2);
+        mailbox.enqueue(msg_2.clone());
+        mailbox.enqueue(message(3, 3));
+        thread::sleep(Duration::from_millis(100));
+
+        let msg_3 = message(3, 6);
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages(), HashMap::from([(3, msg_3)]));
+
+        // the neighbour is back
+        mailbox.enqueue(msg_2.clone());
+        assert_eq!(mailbox.messages().get(&2), Some(&msg_2));
+    }
+
+    #[test]
+    fn test_expire_after_rounds() {
+        let mut mailbox =
+            RetainingMailbox::new(MailboxFactory::memory_less()).expire_after_rounds(2);
+        let msg_2 = message(2, 2);
+        let msg_3 = message(3, 3);
+        mailbox.enqueue(msg_2.clone());
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages().len(), 2);
+
+        // two rounds without updates from 2
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages().len(), 2);
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages().len(), 2);
+
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages(), HashMap::from([(3, msg_3)]));
+    }
+
+    #[test]
+    fn test_drop_undiscovered() {
+        let mut mailbox = RetainingMailbox::new(MailboxFactory::most_recent()).drop_undiscovered();
+        mailbox.enqueue(message(2, 1));
+        let msg_2 = message(2, 2);
+        let msg_3 = message(3, 3);
+        mailbox.enqueue(msg_2.clone());
+        mailbox.enqueue(msg_3.clone());
+        mailbox.update_neighbours(&[1, 2, 3]);
+        assert_eq!(
+            mailbox.messages(),
+            HashMap::from([(2, msg_2), (3, msg_3.clone())])
+        );
+
+        // 2 left, its older message must not be used anymore
+        mailbox.update_neighbours(&[1, 3]);
+        mailbox.enqueue(msg_3.clone());
+        assert_eq!(mailbox.messages(), HashMap::from([(3, msg_3)]));
+    }
 }
@@ -1,17 +1,149 @@
+use async_trait::async_trait;
 use bytes::Bytes;
 use log::info;
-use rf_distributed::network::{sync::Network, NetworkResult, NetworkUpdate};
-use rumqttc::{Client, Event::Incoming, MqttOptions, QoS};
+use rf_distributed::network::{asynchronous, sync::Network, NetworkResult, NetworkUpdate};
+use rumqttc::{AsyncClient, Client, Event::Incoming, Key, LastWill, MqttOptions, QoS, Transport};
 use std::error::Error;
-use std::sync::mpsc::{channel, Receiver};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::task::JoinHandle;
 
+pub mod local;
+pub mod tcp;
+pub mod udp;
+
+/// The topic template used by default, compatible with the devices of previous releases.
+pub const DEFAULT_TOPIC_TEMPLATE: &str = "hello-rufi/{id}/subscriptions";
+/// How long the event loop waits before reconnecting after its first error.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+/// The longest wait between two reconnections, reached by doubling the delay at each error.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// This struct represents the configuration of a network based on MQTT: the connection to the
+/// broker and the topics on which the exports of the devices are published.
+///
+/// The exports of each device are published on a topic obtained from a template, replacing
+/// `{id}` with the id of the device. Deployments sharing a broker can be kept apart by giving
+/// them different prefixes.
+#[derive(Debug, Clone)]
+pub struct MqttNetworkConfig {
+    options: MqttOptions,
+    topic_template: String,
+    publish_qos: QoS,
+    subscribe_qos: QoS,
+    retain: bool,
+    last_will: Option<(String, Vec<u8>)>,
+}
+
+impl MqttNetworkConfig {
+    /// Creates a configuration connecting with the given options, which publishes and subscribes
+    /// at most once on the topics of [DEFAULT_TOPIC_TEMPLATE].
+    pub fn new(options: MqttOptions) -> Self {
+        MqttNetworkConfig {
+            options,
+            topic_template: DEFAULT_TOPIC_TEMPLATE.to_string(),
+            publish_qos: QoS::AtMostOnce,
+            subscribe_qos: QoS::AtMostOnce,
+            retain: false,
+            last_will: None,
+        }
+    }
+
+    /// Sets the template of the topics, which must contain `{id}`.
+    pub fn with_topic_template(mut self, template: impl Into<String>) -> Self {
+        self.topic_template = template.into();
+        self
+    }
+
+    /// Publishes the exports on the topics `{namespace}/{program}/{id}`.
+    pub fn with_prefix(self, namespace: &str, program: &str) -> Self {
+        self.with_topic_template(format!("{namespace}/{program}/{{id}}"))
+    }
+
+    /// Sets the quality of service used to publish the exports and to subscribe to the exports
+    /// of the neighbours.
+    pub fn with_qos(mut self, publish: QoS, subscribe: QoS) -> Self {
+        self.publish_qos = publish;
+        self.subscribe_qos = subscribe;
+        self
+    }
+
+    /// Makes the broker retain the last export of each device, so that a neighbour receives it
+    /// as soon as it subscribes.
+    pub fn with_retained_exports(mut self, retain: bool) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    /// Sets the credentials used to connect to the broker.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.options.set_credentials(username, password);
+        self
+    }
+
+    /// Connects to the broker through TLS.
+    ///
+    /// # Arguments
+    /// * `ca` - The certificate of the authority that signed the certificate of the broker, in
+    ///   PEM format.
+    /// * `client_auth` - The certificate and the private key of the device, if the broker
+    ///   authenticates its clients.
+    pub fn with_tls(mut self, ca: Vec<u8>, client_auth: Option<(Vec<u8>, Key)>) -> Self {
+        self.options
+            .set_transport(Transport::tls(ca, client_auth, None));
+        self
+    }
+
+    /// Sets the message that the broker publishes on the given topic when the device
+    /// disconnects without notice, announcing its departure. The message is published with the
+    /// quality of service of the exports.
+    pub fn with_last_will(mut self, topic: impl Into<String>, payload: impl Into<Vec<u8>>) -> Self {
+        self.last_will = Some((topic.into(), payload.into()));
+        self
+    }
+
+    /// The options used to connect to the broker.
+    pub fn options(&self) -> MqttOptions {
+        let mut options = self.options.clone();
+        if let Some((topic, payload)) = &self.last_will {
+            options.set_last_will(LastWill::new(
+                topic,
+                payload.clone(),
+                self.publish_qos,
+                false,
+            ));
+        }
+        options
+    }
+
+    /// The topic on which the exports of the given device are published.
+    pub fn topic(&self, id: i32) -> String {
+        self.topic_template.replace("{id}", &id.to_string())
+    }
+
+    fn validate(&self) -> NetworkResult<()> {
+        if self.topic_template.contains("{id}") {
+            Ok(())
+        } else {
+            Err("The topic template must contain {id}".into())
+        }
+    }
+}
 
 /// This struct represent the network that will be used to send and receive messages
 /// using the MQTT protocol.
+///
+/// The MQTT connection is handled by a thread, which stops at its next notification once the
+/// network is dropped.
 pub struct SyncMQTTNetwork {
     client: Client,
     receiver: Receiver<NetworkUpdate>,
+    config: MqttNetworkConfig,
+    stop: Arc<AtomicBool>,
 }
 
 impl SyncMQTTNetwork {
@@ -20,16 +152,34 @@ impl SyncMQTTNetwork {
         topics: Vec<i32>,
         mqtt_channel_cap: usize,
     ) -> Result<Self, Box<dyn Error>> {
-        let (mut client, mut connection) = Client::new(options, mqtt_channel_cap);
-        SyncMQTTNetwork::subscribe_to_topics(&mut client, topics)?;
+        SyncMQTTNetwork::with_config(MqttNetworkConfig::new(options), topics, mqtt_channel_cap)
+    }
+
+    /// Creates a new network with the given configuration, subscribing to the exports of the
+    /// given neighbours.
+    pub fn with_config(
+        config: MqttNetworkConfig,
+        topics: Vec<i32>,
+        mqtt_channel_cap: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        config.validate()?;
+        let (mut client, mut connection) = Client::new(config.options(), mqtt_channel_cap);
+        SyncMQTTNetwork::subscribe_to_topics(&mut client, &config, topics)?;
         let (sender, receiver) = channel::<NetworkUpdate>();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
         thread::spawn(move || {
+            let mut delay = MIN_RECONNECT_DELAY;
             loop {
-                for (_i, notification) in connection.iter().enumerate() {
+                for notification in connection.iter() {
+                    if stopped.load(Ordering::SeqCst) {
+                        // Dropping the connection closes it
+                        return;
+                    }
                     match notification {
                         Ok(Incoming(rumqttc::Packet::Publish(msg))) => {
-                            if let Err(send_error) = sender
-                                .send(NetworkUpdate::Update { msg: msg.payload })
+                            if let Err(send_error) =
+                                sender.send(NetworkUpdate::Update { msg: msg.payload })
                             {
                                 info!(
                                     "Error sending message to receiver: {:?}",
@@ -41,32 +191,41 @@ impl SyncMQTTNetwork {
                             sender
                                 .send(NetworkUpdate::Err {
                                     reason: "Disconnected".to_string(),
-                                }).unwrap_or(()); // Ignore the error
+                                })
+                                .unwrap_or(()); // Ignore the error
                         }
                         Err(e) => {
-                            if let Err(e2) = sender
-                                .send(NetworkUpdate::Err {
-                                    reason: e.to_string(),
-                                })
-                            {
+                            if let Err(e2) = sender.send(NetworkUpdate::Err {
+                                reason: e.to_string(),
+                            }) {
                                 info!("Error: {:?}", e2.to_string());
                             }
+                            // The next notification reconnects to the broker
+                            thread::sleep(delay);
+                            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                            continue;
                         }
                         _ => {}
                     }
+                    delay = MIN_RECONNECT_DELAY;
                 }
             }
         });
-        Ok(Self { client, receiver })
+        Ok(Self {
+            client,
+            receiver,
+            config,
+            stop,
+        })
     }
 
-    fn subscribe_to_topics(client: &mut Client, topics: Vec<i32>) -> NetworkResult<()> {
-        for nbr in topics.clone() {
-            if let Err(e) = client
-                .subscribe(format!("hello-rufi/{nbr}/subscriptions"), QoS::AtMostOnce)
-            {
-                return Err(e.into());
-            }
+    fn subscribe_to_topics(
+        client: &mut Client,
+        config: &MqttNetworkConfig,
+        topics: Vec<i32>,
+    ) -> NetworkResult<()> {
+        for nbr in topics {
+            client.subscribe(config.topic(nbr), config.subscribe_qos)?;
         }
         Ok(())
     }
@@ -76,9 +235,9 @@ impl Network for SyncMQTTNetwork {
     fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
         self.client
             .try_publish(
-                format!("hello-rufi/{source}/subscriptions"),
-                QoS::AtMostOnce,
-                false,
+                self.config.topic(source),
+                self.config.publish_qos,
+                self.config.retain,
                 msg,
             )
             .map_err(|e| e.into())
@@ -89,4 +248,195 @@ impl Network for SyncMQTTNetwork {
             .recv()
             .map_err(|_e| "No message received".into())
     }
+
+    fn receive_timeout(&mut self, timeout: Duration) -> NetworkResult<NetworkUpdate> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(update) => Ok(update),
+            Err(RecvTimeoutError::Timeout) => Ok(NetworkUpdate::None),
+            Err(RecvTimeoutError::Disconnected) => Err("No message received".into()),
+        }
+    }
+
+    fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.client
+            .subscribe(self.config.topic(nbr), self.config.subscribe_qos)
+            .map_err(|e| e.into())
+    }
+
+    fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.client
+            .unsubscribe(self.config.topic(nbr))
+            .map_err(|e| e.into())
+    }
+}
+
+/// This struct represent the network that will be used to send and receive messages
+/// using the MQTT protocol, without blocking the thread it runs on.
+///
+/// The MQTT event loop is polled by a tokio task, which lives as long as the network.
+pub struct AsyncMQTTNetwork {
+    client: AsyncClient,
+    receiver: UnboundedReceiver<NetworkUpdate>,
+    config: MqttNetworkConfig,
+    task: JoinHandle<()>,
+}
+
+impl AsyncMQTTNetwork {
+    pub async fn new(
+        options: MqttOptions,
+        topics: Vec<i32>,
+        mqtt_channel_cap: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        AsyncMQTTNetwork::with_config(MqttNetworkConfig::new(options), topics, mqtt_channel_cap)
+            .await
+    }
+
+    /// Creates a new network with the given configuration, subscribing to the exports of the
+    /// given neighbours.
+    pub async fn with_config(
+        config: MqttNetworkConfig,
+        topics: Vec<i32>,
+        mqtt_channel_cap: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        config.validate()?;
+        let (client, mut event_loop) = AsyncClient::new(config.options(), mqtt_channel_cap);
+        let (sender, receiver) = unbounded_channel::<NetworkUpdate>();
+        let task = tokio::spawn(async move {
+            let mut delay = MIN_RECONNECT_DELAY;
+            loop {
+                let update = match event_loop.poll().await {
+                    Ok(Incoming(rumqttc::Packet::Publish(msg))) => {
+                        NetworkUpdate::Update { msg: msg.payload }
+                    }
+                    Ok(Incoming(rumqttc::Packet::Disconnect)) => NetworkUpdate::Err {
+                        reason: "Disconnected".to_string(),
+                    },
+                    Ok(_) => {
+                        delay = MIN_RECONNECT_DELAY;
+                        continue;
+                    }
+                    Err(e) => {
+                        if sender
+                            .send(NetworkUpdate::Err {
+                                reason: e.to_string(),
+                            })
+                            .is_err()
+                        {
+                            break;
+                        }
+                        // The next poll reconnects to the broker
+                        tokio::time::sleep(delay).await;
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                        continue;
+                    }
+                };
+                delay = MIN_RECONNECT_DELAY;
+                if sender.send(update).is_err() {
+                    // The network has been dropped
+                    break;
+                }
+            }
+        });
+        let mut network = Self {
+            client,
+            receiver,
+            config,
+            task,
+        };
+        for nbr in topics {
+            asynchronous::Network::subscribe(&mut network, nbr).await?;
+        }
+        Ok(network)
+    }
+}
+
+#[async_trait]
+impl asynchronous::Network for AsyncMQTTNetwork {
+    async fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.client
+            .publish_bytes(
+                self.config.topic(source),
+                self.config.publish_qos,
+                self.config.retain,
+                msg,
+            )
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.receiver
+            .recv()
+            .await
+            .ok_or_else(|| "No message received".into())
+    }
+
+    async fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.client
+            .subscribe(self.config.topic(nbr), self.config.subscribe_qos)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    async fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.client
+            .unsubscribe(self.config.topic(nbr))
+            .await
+            .map_err(|e| e.into())
+    }
+}
+
+impl Drop for SyncMQTTNetwork {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for AsyncMQTTNetwork {
+    fn drop(&mut self) {
+        // The event loop keeps the connection open even without clients, so it is stopped here
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> MqttNetworkConfig {
+        MqttNetworkConfig::new(MqttOptions::new("device#1", "localhost", 1883))
+    }
+
+    #[test]
+    fn test_topics() {
+        assert_eq!(config().topic(1), "hello-rufi/1/subscriptions");
+        assert_eq!(
+            config().with_prefix("building-a", "gradient").topic(2),
+            "building-a/gradient/2"
+        );
+        assert!(config()
+            .with_topic_template("rufi/devices")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_connection_options() {
+        // The last will follows the quality of service set afterwards
+        let config = config()
+            .with_last_will("building-a/departures", "1")
+            .with_qos(QoS::AtLeastOnce, QoS::ExactlyOnce)
+            .with_credentials("user", "secret")
+            .with_tls(b"ca".to_vec(), None);
+        let options = config.options();
+        assert_eq!(
+            options.credentials(),
+            Some(("user".to_string(), "secret".to_string()))
+        );
+        let will = options.last_will().unwrap();
+        assert_eq!(will.topic, "building-a/departures");
+        assert_eq!(will.message, Bytes::from("1"));
+        assert_eq!(will.qos, QoS::AtLeastOnce);
+        assert!(matches!(options.transport(), Transport::Tls(_)));
+    }
 }
@@ -0,0 +1,312 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use rf_distributed::network::{asynchronous, sync, NetworkResult, NetworkUpdate};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+#[derive(Default)]
+struct Messages {
+    queue: VecDeque<Bytes>,
+    closed: bool,
+}
+
+/// The messages delivered to a device, which can be waited for both by blocking the thread and
+/// by awaiting them. Once closed, the messages already delivered can still be received.
+#[derive(Default)]
+struct Inbox {
+    messages: Mutex<Messages>,
+    delivered: Condvar,
+    notify: Notify,
+}
+
+impl Inbox {
+    fn push(&self, msg: Bytes) {
+        self.messages.lock().unwrap().queue.push_back(msg);
+        self.wake();
+    }
+
+    fn close(&self) {
+        self.messages.lock().unwrap().closed = true;
+        self.wake();
+    }
+
+    fn wake(&self) {
+        self.delivered.notify_all();
+        self.notify.notify_one();
+    }
+
+    fn pop(messages: &mut Messages) -> NetworkResult<Option<Bytes>> {
+        match messages.queue.pop_front() {
+            Some(msg) => Ok(Some(msg)),
+            None if messages.closed => Err("The device has been replaced".into()),
+            None => Ok(None),
+        }
+    }
+
+    fn try_receive(&self) -> NetworkResult<Option<Bytes>> {
+        Inbox::pop(&mut self.messages.lock().unwrap())
+    }
+
+    /// Blocks the thread until a message is delivered or, if given, the timeout expires.
+    fn receive_blocking(&self, timeout: Option<Duration>) -> NetworkResult<Option<Bytes>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut messages = self.messages.lock().unwrap();
+        loop {
+            if let Some(msg) = Inbox::pop(&mut messages)? {
+                return Ok(Some(msg));
+            }
+            messages = match deadline {
+                None => self.delivered.wait(messages).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    self.delivered
+                        .wait_timeout(messages, deadline - now)
+                        .unwrap()
+                        .0
+                }
+            };
+        }
+    }
+
+    async fn receive(&self) -> NetworkResult<Bytes> {
+        loop {
+            // A notification sent after the check is not lost, since it stores a permit
+            let notified = self.notify.notified();
+            if let Some(msg) = self.try_receive()? {
+                return Ok(msg);
+            }
+            notified.await;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Links {
+    inboxes: HashMap<i32, Weak<Inbox>>,
+    adjacency: HashMap<i32, HashSet<i32>>,
+}
+
+/// This struct connects any number of devices running in the same process through channels, so
+/// that platforms can be tested without a broker.
+///
+/// The messages sent by a device are delivered to the devices linked to it and to the device
+/// itself, as a broker does when the device subscribes to its own topic. The hub can be cloned,
+/// and every clone shares the same devices and links.
+#[derive(Clone, Default)]
+pub struct NetworkHub {
+    links: Arc<Mutex<Links>>,
+}
+
+impl NetworkHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a hub whose devices are linked according to the given adjacency list.
+    pub fn with_adjacency(adjacency: HashMap<i32, Vec<i32>>) -> Self {
+        let hub = Self::new();
+        for (id, nbrs) in adjacency {
+            for nbr in nbrs {
+                hub.link(id, nbr);
+            }
+        }
+        hub
+    }
+
+    /// Creates the network of a device. If the device already had a network, the messages sent
+    /// to it are delivered to the new one only.
+    pub fn network(&self, id: i32) -> LocalNetwork {
+        let inbox = Arc::new(Inbox::default());
+        let replaced = self
+            .links
+            .lock()
+            .unwrap()
+            .inboxes
+            .insert(id, Arc::downgrade(&inbox));
+        if let Some(replaced) = replaced.and_then(|replaced| replaced.upgrade()) {
+            replaced.close();
+        }
+        LocalNetwork {
+            hub: self.clone(),
+            inbox,
+        }
+    }
+
+    /// Links two devices, so that each of them receives the messages of the other.
+    pub fn link(&self, a: i32, b: i32) {
+        let mut links = self.links.lock().unwrap();
+        links.adjacency.entry(a).or_default().insert(b);
+        links.adjacency.entry(b).or_default().insert(a);
+    }
+
+    /// Removes the link between two devices.
+    pub fn unlink(&self, a: i32, b: i32) {
+        let mut links = self.links.lock().unwrap();
+        links.adjacency.entry(a).or_default().remove(&b);
+        links.adjacency.entry(b).or_default().remove(&a);
+    }
+
+    /// The devices linked to the given one.
+    pub fn neighbours(&self, id: i32) -> Vec<i32> {
+        let links = self.links.lock().unwrap();
+        let mut nbrs: Vec<i32> = links
+            .adjacency
+            .get(&id)
+            .map(|nbrs| nbrs.iter().cloned().collect())
+            .unwrap_or_default();
+        nbrs.sort();
+        nbrs
+    }
+
+    fn deliver(&self, source: i32, msg: Bytes) {
+        let links = self.links.lock().unwrap();
+        let nbrs = links.adjacency.get(&source).into_iter().flatten();
+        for id in nbrs.chain([&source]) {
+            // The device may have been dropped
+            if let Some(inbox) = links.inboxes.get(id).and_then(|inbox| inbox.upgrade()) {
+                inbox.push(msg.clone());
+            }
+        }
+    }
+}
+
+/// This struct represent the network of a device connected to a [NetworkHub].
+///
+/// The synchronous [sync::Network::receive] blocks until a message arrives, so it must not be
+/// called inside a tokio runtime.
+pub struct LocalNetwork {
+    hub: NetworkHub,
+    inbox: Arc<Inbox>,
+}
+
+/// Wraps a message received by a [LocalNetwork], if any, into an update.
+fn update(msg: Option<Bytes>) -> NetworkUpdate {
+    msg.map_or(NetworkUpdate::None, |msg| NetworkUpdate::Update { msg })
+}
+
+impl sync::Network for LocalNetwork {
+    fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.hub.deliver(source, msg);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.inbox.receive_blocking(None).map(update)
+    }
+
+    fn try_receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.inbox.try_receive().map(update)
+    }
+
+    fn receive_timeout(&mut self, timeout: Duration) -> NetworkResult<NetworkUpdate> {
+        self.inbox.receive_blocking(Some(timeout)).map(update)
+    }
+}
+
+#[async_trait]
+impl asynchronous::Network for LocalNetwork {
+    async fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.hub.deliver(source, msg);
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.inbox
+            .receive()
+            .await
+            .map(|msg| NetworkUpdate::Update { msg })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rf_distributed::network::sync::Network;
+    use std::thread;
+
+    fn received(network: &mut LocalNetwork) -> Vec<Bytes> {
+        let mut msgs = vec![];
+        while let Ok(NetworkUpdate::Update { msg }) = network.try_receive() {
+            msgs.push(msg);
+        }
+        msgs
+    }
+
+    #[test]
+    fn test_adjacency() {
+        // Topology: [1] -- [2] -- [3]
+        let hub = NetworkHub::with_adjacency(HashMap::from([(1, vec![2]), (2, vec![3])]));
+        let mut networks: HashMap<i32, LocalNetwork> =
+            (1..=3).map(|id| (id, hub.network(id))).collect();
+        assert_eq!(hub.neighbours(2), vec![1, 3]);
+
+        let msg = Bytes::from("export of 1");
+        networks.get_mut(&1).unwrap().send(1, msg.clone()).unwrap();
+        assert_eq!(received(networks.get_mut(&1).unwrap()), vec![msg.clone()]);
+        assert_eq!(received(networks.get_mut(&2).unwrap()), vec![msg]);
+        assert!(received(networks.get_mut(&3).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_unlink() {
+        let hub = NetworkHub::new();
+        let mut network_1 = hub.network(1);
+        let mut network_2 = hub.network(2);
+        hub.link(1, 2);
+        network_1.send(1, Bytes::from("first")).unwrap();
+        hub.unlink(1, 2);
+        network_1.send(1, Bytes::from("second")).unwrap();
+
+        assert_eq!(received(&mut network_2), vec![Bytes::from("first")]);
+        assert_eq!(hub.neighbours(1), Vec::<i32>::new());
+        assert_eq!(received(&mut network_1).len(), 2);
+    }
+
+    #[test]
+    fn test_receive_timeout() {
+        let hub = NetworkHub::new();
+        let mut network_1 = hub.network(1);
+        let mut network_2 = hub.network(2);
+        hub.link(1, 2);
+        let timeout = Duration::from_millis(20);
+        let start = Instant::now();
+        assert!(matches!(
+            network_2.receive_timeout(timeout),
+            Ok(NetworkUpdate::None)
+        ));
+        assert!(start.elapsed() >= timeout);
+
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            network_1.send(1, Bytes::from("export of 1")).unwrap();
+        });
+        let update = network_2.receive_timeout(Duration::from_secs(1));
+        assert!(matches!(update, Ok(NetworkUpdate::Update { msg }) if msg == "export of 1"));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn test_receive_blocks() {
+        let hub = NetworkHub::new();
+        let mut network_1 = hub.network(1);
+        let mut network_2 = hub.network(2);
+        hub.link(1, 2);
+
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            network_1.send(1, Bytes::from("export of 1")).unwrap();
+        });
+        let update = network_2.receive();
+        assert!(matches!(update, Ok(NetworkUpdate::Update { msg }) if msg == "export of 1"));
+        sender.join().unwrap();
+
+        // The network of a replaced device is disconnected
+        let _replacement = hub.network(2);
+        assert!(network_2.receive().is_err());
+    }
+}
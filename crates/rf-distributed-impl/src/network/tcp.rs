@@ -0,0 +1,326 @@
+use bytes::Bytes;
+use log::info;
+use rf_distributed::discovery::AddressDiscovery;
+use rf_distributed::network::{sync::Network, NetworkResult, NetworkUpdate};
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The largest frame accepted from a peer.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+/// The time waited for a neighbour to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// The time a send waits for a pending connection. A connection that takes longer is completed
+/// in the background, and used by a later send.
+const CONNECT_DEADLINE: Duration = Duration::from_millis(50);
+/// The time waited for a neighbour to make room for a frame before the connection is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The inbound connections of the peers, by the order in which they were accepted, so that they
+/// can be closed when the network is dropped.
+type Inbound = Arc<Mutex<HashMap<usize, TcpStream>>>;
+
+/// Writes a message prefixed by its length, as a big endian `u32`.
+fn write_frame(stream: &mut TcpStream, msg: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(msg.len())
+        .ok()
+        .filter(|len| *len as usize <= MAX_FRAME_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "The message is too large"))?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(msg)
+}
+
+/// Reads a message prefixed by its length, as a big endian `u32`.
+fn read_frame(stream: &mut TcpStream) -> io::Result<Bytes> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The frame is too large",
+        ));
+    }
+    let mut msg = vec![0; len];
+    stream.read_exact(&mut msg)?;
+    Ok(Bytes::from(msg))
+}
+
+/// Receives the frames of an inbound peer until it disconnects.
+fn serve(mut stream: TcpStream, sender: Sender<NetworkUpdate>) {
+    while let Ok(msg) = read_frame(&mut stream) {
+        if sender.send(NetworkUpdate::Update { msg }).is_err() {
+            // The network has been dropped
+            break;
+        }
+    }
+}
+
+/// The outbound connection to a neighbour. When the connection fails, it is attempted again
+/// after a delay that doubles at each failure.
+struct Peer {
+    address: SocketAddr,
+    stream: Option<TcpStream>,
+    connecting: Option<Receiver<io::Result<TcpStream>>>,
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl Peer {
+    fn new(address: SocketAddr, delay: Duration) -> Self {
+        Peer {
+            address,
+            stream: None,
+            connecting: None,
+            delay,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    /// Connects to the neighbour in a background thread, waiting at most [CONNECT_DEADLINE] for
+    /// the connection to be established.
+    fn connect(&mut self, min_delay: Duration, max_delay: Duration) -> io::Result<()> {
+        let connecting = match self.connecting.take() {
+            Some(connecting) => connecting,
+            None if Instant::now() < self.next_attempt => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "Waiting to reconnect",
+                ));
+            }
+            None => {
+                let (sender, receiver) = channel();
+                let address = self.address;
+                thread::spawn(move || {
+                    sender.send(TcpStream::connect_timeout(&address, CONNECT_TIMEOUT))
+                });
+                receiver
+            }
+        };
+        let connected = match connecting.recv_timeout(CONNECT_DEADLINE) {
+            Ok(connected) => connected,
+            Err(RecvTimeoutError::Timeout) => {
+                self.connecting = Some(connecting);
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "Still connecting",
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(io::Error::other("The connection attempt was aborted"))
+            }
+        };
+        let configured = connected.and_then(|stream| {
+            stream.set_nodelay(true)?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            Ok(stream)
+        });
+        match configured {
+            Ok(stream) => {
+                self.stream = Some(stream);
+                self.delay = min_delay;
+                Ok(())
+            }
+            Err(e) => {
+                self.backoff(max_delay);
+                Err(e)
+            }
+        }
+    }
+
+    fn send(&mut self, msg: &[u8], min_delay: Duration, max_delay: Duration) -> io::Result<()> {
+        if self.stream.is_none() {
+            self.connect(min_delay, max_delay)?;
+        }
+        let result = self
+            .stream
+            .as_mut()
+            .map_or(Ok(()), |stream| write_frame(stream, msg));
+        if result.is_err() {
+            self.stream = None;
+            self.backoff(max_delay);
+        }
+        result
+    }
+
+    fn backoff(&mut self, max_delay: Duration) {
+        self.next_attempt = Instant::now() + self.delay;
+        self.delay = (self.delay * 2).min(max_delay);
+    }
+}
+
+/// This struct represent the network that will be used to send and receive messages through
+/// persistent TCP connections between neighbours, without any broker.
+///
+/// The device connects to the neighbours it subscribes to, at the addresses provided by the
+/// discovery, and accepts the connections of any peer. The address of a neighbour that cannot be
+/// reached is looked up again, in case the neighbour has moved. Each message is sent as a frame prefixed
+/// by its length. A neighbour that does not accept a frame within a short timeout is
+/// disconnected, so that it cannot stall the rounds of the device.
+pub struct TcpNetwork<D: AddressDiscovery> {
+    discovery: D,
+    peers: HashMap<i32, Peer>,
+    receiver: Receiver<NetworkUpdate>,
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+    inbound: Inbound,
+    min_delay: Duration,
+    max_delay: Duration,
+}
+
+impl<D: AddressDiscovery> TcpNetwork<D> {
+    /// Creates a new network, accepting the connections of the peers on the given address.
+    pub fn new(address: SocketAddr, discovery: D) -> NetworkResult<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel::<NetworkUpdate>();
+        let inbound = Inbound::default();
+        let stop = shutdown.clone();
+        let accepted = inbound.clone();
+        let acceptor = thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                match stream.and_then(|stream| Ok((stream.try_clone()?, stream))) {
+                    Ok((clone, stream)) => {
+                        accepted.lock().unwrap().insert(id, clone);
+                        let sender = sender.clone();
+                        let accepted = accepted.clone();
+                        thread::spawn(move || {
+                            serve(stream, sender);
+                            accepted.lock().unwrap().remove(&id);
+                        });
+                    }
+                    Err(e) => info!("Error accepting a peer: {:?}", e.to_string()),
+                }
+            }
+        });
+        Ok(TcpNetwork {
+            discovery,
+            peers: HashMap::new(),
+            receiver,
+            local_addr,
+            shutdown,
+            acceptor: Some(acceptor),
+            inbound,
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+        })
+    }
+
+    /// Sets the delay before the first reconnection to a neighbour and the largest delay
+    /// between two reconnections.
+    pub fn with_backoff(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// The local address on which the device accepts the connections of the peers.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Replaces the connection to a neighbour that cannot be reached if the discovery reports a
+    /// different address for it.
+    fn relocate(&mut self, nbr: i32) {
+        let Some(address) = self.discovery.address_of(nbr) else {
+            return;
+        };
+        if self
+            .peers
+            .get(&nbr)
+            .is_some_and(|peer| peer.address != address)
+        {
+            self.peers.insert(nbr, Peer::new(address, self.min_delay));
+        }
+    }
+}
+
+impl<D: AddressDiscovery> Network for TcpNetwork<D> {
+    fn send(&mut self, _source: i32, msg: Bytes) -> NetworkResult<()> {
+        let mut failed = vec![];
+        for (nbr, peer) in self.peers.iter_mut() {
+            if let Err(e) = peer.send(&msg, self.min_delay, self.max_delay) {
+                info!("Error sending to {}: {:?}", nbr, e.to_string());
+                failed.push(*nbr);
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            failed.sort();
+            for nbr in failed.iter() {
+                self.relocate(*nbr);
+            }
+            Err(format!("Cannot reach the neighbours {:?}", failed).into())
+        }
+    }
+
+    fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.receiver
+            .recv()
+            .map_err(|_e| "No message received".into())
+    }
+
+    fn receive_timeout(&mut self, timeout: Duration) -> NetworkResult<NetworkUpdate> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(update) => Ok(update),
+            Err(RecvTimeoutError::Timeout) => Ok(NetworkUpdate::None),
+            Err(RecvTimeoutError::Disconnected) => Err("No message received".into()),
+        }
+    }
+
+    /// Connects to the neighbour at the address provided by the discovery, failing if it is not
+    /// known yet.
+    fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        let address = self
+            .discovery
+            .address_of(nbr)
+            .ok_or_else(|| format!("The address of {} is unknown", nbr))?;
+        let min_delay = self.min_delay;
+        self.peers
+            .entry(nbr)
+            .or_insert_with(|| Peer::new(address, min_delay));
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.peers.remove(&nbr);
+        Ok(())
+    }
+}
+
+impl<D: AddressDiscovery> Drop for TcpNetwork<D> {
+    fn drop(&mut self) {
+        // Wake the thread accepting the peers up, so that it releases the address. A listener
+        // bound to every interface is reached through the loopback one
+        self.shutdown.store(true, Ordering::SeqCst);
+        let mut address = self.local_addr;
+        if address.ip().is_unspecified() {
+            address.set_ip(match address {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        if TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).is_ok() {
+            if let Some(acceptor) = self.acceptor.take() {
+                let _ = acceptor.join();
+            }
+        }
+        // Close the inbound connections, so that the threads serving them stop
+        for stream in self.inbound.lock().unwrap().values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
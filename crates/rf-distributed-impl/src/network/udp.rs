@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use bytes::{BufMut, Bytes, BytesMut};
+use rf_distributed::network::{asynchronous, sync, NetworkResult, NetworkUpdate};
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// The largest payload of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// The way the exports of the device reach its neighbours.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UdpMode {
+    /// Every export is sent to the given broadcast address, e.g. `255.255.255.255` or the
+    /// broadcast address of the subnet, on the port of the configuration.
+    Broadcast(Ipv4Addr),
+    /// Every export is sent to the given multicast group, which the device joins, on the port of
+    /// the configuration.
+    Multicast(Ipv4Addr),
+    /// Every export is sent to each of the given addresses of the neighbours, and to the address
+    /// of the device itself.
+    Unicast(HashMap<i32, SocketAddr>),
+}
+
+/// This struct represents the configuration of a network based on UDP.
+///
+/// With broadcast and multicast every device must use the same port, and many devices can run
+/// on the same host since the port is bound with `SO_REUSEADDR`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UdpConfig {
+    /// The port on which the device receives the exports.
+    pub port: u16,
+    /// The address of the interface on which the device sends and receives the exports.
+    pub interface: Ipv4Addr,
+    pub mode: UdpMode,
+}
+
+impl UdpConfig {
+    /// Creates a configuration that uses any interface.
+    pub fn new(port: u16, mode: UdpMode) -> Self {
+        UdpConfig {
+            port,
+            interface: Ipv4Addr::UNSPECIFIED,
+            mode,
+        }
+    }
+
+    /// Sets the interface on which the device sends and receives the exports.
+    pub fn with_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.interface = interface;
+        self
+    }
+
+    fn bind(&self) -> io::Result<UdpSocket> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        let address = match self.mode {
+            UdpMode::Broadcast(_) => {
+                socket.set_reuse_address(true)?;
+                socket.set_broadcast(true)?;
+                // Broadcasts are only received by sockets bound to the wildcard address
+                Ipv4Addr::UNSPECIFIED
+            }
+            UdpMode::Multicast(group) => {
+                socket.set_reuse_address(true)?;
+                socket.join_multicast_v4(&group, &self.interface)?;
+                socket.set_multicast_if_v4(&self.interface)?;
+                Ipv4Addr::UNSPECIFIED
+            }
+            UdpMode::Unicast(_) => self.interface,
+        };
+        socket.bind(&SockAddr::from(SocketAddrV4::new(address, self.port)))?;
+        Ok(socket.into())
+    }
+
+    fn destinations(&self, socket: &UdpSocket) -> io::Result<Vec<SocketAddr>> {
+        match &self.mode {
+            UdpMode::Broadcast(address) | UdpMode::Multicast(address) => {
+                Ok(vec![SocketAddrV4::new(*address, self.port).into()])
+            }
+            UdpMode::Unicast(nbrs) => {
+                // Broadcasts and multicasts reach the device itself, while unicasts must be sent
+                // to it explicitly
+                let mut own = socket.local_addr()?;
+                if own.ip().is_unspecified() {
+                    own.set_ip(Ipv4Addr::LOCALHOST.into());
+                }
+                let mut destinations: Vec<SocketAddr> = nbrs.values().cloned().collect();
+                if !destinations.contains(&own) {
+                    destinations.push(own);
+                }
+                Ok(destinations)
+            }
+        }
+    }
+}
+
+/// Prepends the id of the source to the message, so that devices can tell the senders of the
+/// datagrams apart without decoding them.
+fn frame(source: i32, msg: Bytes) -> NetworkResult<Bytes> {
+    if msg.len() + 4 > MAX_DATAGRAM_SIZE {
+        return Err("The message is too large for a UDP datagram".into());
+    }
+    let mut datagram = BytesMut::with_capacity(msg.len() + 4);
+    datagram.put_i32(source);
+    datagram.put(msg);
+    Ok(datagram.freeze())
+}
+
+/// Extracts the id of the source and the message from a datagram, if it was sent by a device.
+fn unframe(datagram: &[u8]) -> Option<(i32, Bytes)> {
+    let source = i32::from_be_bytes(datagram.get(..4)?.try_into().ok()?);
+    Some((source, Bytes::copy_from_slice(&datagram[4..])))
+}
+
+/// The devices whose datagrams are delivered: the subscribed neighbours and the device itself.
+#[derive(Default)]
+struct Sources {
+    nbrs: HashSet<i32>,
+    self_id: Option<i32>,
+}
+
+impl Sources {
+    /// Extracts the message from a datagram, if it was sent by one of the sources.
+    fn accept(&self, datagram: &[u8]) -> Option<Bytes> {
+        unframe(datagram)
+            .filter(|(source, _)| self.nbrs.contains(source) || self.self_id == Some(*source))
+            .map(|(_, msg)| msg)
+    }
+}
+
+/// This struct represent the network that will be used to send and receive messages through
+/// UDP datagrams, without any broker.
+///
+/// Only the datagrams of the subscribed neighbours and of the device itself are received.
+pub struct UdpNetwork {
+    socket: UdpSocket,
+    destinations: Vec<SocketAddr>,
+    sources: Sources,
+}
+
+impl UdpNetwork {
+    pub fn new(config: UdpConfig) -> NetworkResult<Self> {
+        let socket = config.bind()?;
+        Ok(UdpNetwork {
+            destinations: config.destinations(&socket)?,
+            socket,
+            sources: Sources::default(),
+        })
+    }
+
+    /// The local address on which the device receives the exports.
+    pub fn local_addr(&self) -> NetworkResult<SocketAddr> {
+        self.socket.local_addr().map_err(|e| e.into())
+    }
+}
+
+impl sync::Network for UdpNetwork {
+    fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.sources.self_id = Some(source);
+        let datagram = frame(source, msg)?;
+        for destination in self.destinations.iter() {
+            self.socket.send_to(&datagram, destination)?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        let mut buffer = [0; MAX_DATAGRAM_SIZE];
+        loop {
+            let (len, _) = self.socket.recv_from(&mut buffer)?;
+            if let Some(msg) = self.sources.accept(&buffer[..len]) {
+                return Ok(NetworkUpdate::Update { msg });
+            }
+        }
+    }
+
+    fn receive_timeout(&mut self, timeout: Duration) -> NetworkResult<NetworkUpdate> {
+        let deadline = Instant::now() + timeout;
+        let mut buffer = [0; MAX_DATAGRAM_SIZE];
+        let result = loop {
+            // The socket rejects a zero timeout, so the pending datagrams are read without blocking
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                self.socket.set_nonblocking(true)?;
+            } else {
+                self.socket.set_read_timeout(Some(remaining))?;
+            }
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, _)) => match self.sources.accept(&buffer[..len]) {
+                    Some(msg) => break Ok(NetworkUpdate::Update { msg }),
+                    None => continue,
+                },
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    break Ok(NetworkUpdate::None)
+                }
+                Err(e) => break Err(e.into()),
+            }
+        };
+        self.socket.set_nonblocking(false)?;
+        self.socket.set_read_timeout(None)?;
+        result
+    }
+
+    fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.sources.nbrs.insert(nbr);
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.sources.nbrs.remove(&nbr);
+        Ok(())
+    }
+}
+
+/// This struct represent the network that will be used to send and receive messages through
+/// UDP datagrams, without any broker and without blocking the thread it runs on.
+///
+/// Only the datagrams of the subscribed neighbours and of the device itself are received.
+pub struct AsyncUdpNetwork {
+    socket: tokio::net::UdpSocket,
+    destinations: Vec<SocketAddr>,
+    sources: Sources,
+}
+
+impl AsyncUdpNetwork {
+    /// Creates the network. It must be called inside a tokio runtime.
+    pub fn new(config: UdpConfig) -> NetworkResult<Self> {
+        let socket = config.bind()?;
+        socket.set_nonblocking(true)?;
+        Ok(AsyncUdpNetwork {
+            destinations: config.destinations(&socket)?,
+            socket: tokio::net::UdpSocket::from_std(socket)?,
+            sources: Sources::default(),
+        })
+    }
+
+    /// The local address on which the device receives the exports.
+    pub fn local_addr(&self) -> NetworkResult<SocketAddr> {
+        self.socket.local_addr().map_err(|e| e.into())
+    }
+}
+
+#[async_trait]
+impl asynchronous::Network for AsyncUdpNetwork {
+    async fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.sources.self_id = Some(source);
+        let datagram = frame(source, msg)?;
+        for destination in self.destinations.iter() {
+            self.socket.send_to(&datagram, destination).await?;
+        }
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+        loop {
+            let (len, _) = self.socket.recv_from(&mut buffer).await?;
+            if let Some(msg) = self.sources.accept(&buffer[..len]) {
+                return Ok(NetworkUpdate::Update { msg });
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.sources.nbrs.insert(nbr);
+        Ok(())
+    }
+
+    async fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+        self.sources.nbrs.remove(&nbr);
+        Ok(())
+    }
+}
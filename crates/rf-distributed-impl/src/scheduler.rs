@@ -0,0 +1,282 @@
+use rand::Rng;
+use rf_core::export::Export;
+use rf_distributed::scheduler::{Event, RoundReport, Scheduler};
+use std::time::{Duration, Instant};
+
+/// The shortest time between the start of two rounds of [EventTriggered], unless it is set.
+pub const DEFAULT_MIN_DELAY: Duration = Duration::from_millis(50);
+
+/// This struct represents a policy executing a round every `period`. The time spent executing
+/// the round is subtracted from the delay, so that the rounds do not drift.
+pub struct FixedRate {
+    period: Duration,
+}
+
+impl FixedRate {
+    pub fn new(period: Duration) -> Self {
+        FixedRate { period }
+    }
+}
+
+impl Scheduler for FixedRate {
+    fn next_round(&mut self, round: &RoundReport) -> Duration {
+        self.period.saturating_sub(round.duration)
+    }
+}
+
+/// This struct represents a policy executing a round every `period` on average, moving each
+/// round by a random offset of at most `jitter`. It prevents the devices that started together
+/// from sending their exports in synchronized bursts.
+pub struct JitteredRate {
+    rate: FixedRate,
+    jitter: Duration,
+}
+
+impl JitteredRate {
+    pub fn new(period: Duration, jitter: Duration) -> Self {
+        JitteredRate {
+            rate: FixedRate::new(period),
+            jitter,
+        }
+    }
+}
+
+impl Scheduler for JitteredRate {
+    fn next_round(&mut self, round: &RoundReport) -> Duration {
+        let delay = self.rate.next_round(round);
+        let offset = self.jitter.mul_f64(rand::thread_rng().gen_range(0.0..2.0));
+        (delay + offset).saturating_sub(self.jitter)
+    }
+}
+
+/// This struct represents a policy that slows the rounds down while the export of the device
+/// does not change, and goes back to the shortest period as soon as it does.
+///
+/// Each round with the same export as the previous one multiplies the period by `factor`, up to
+/// `max_period`.
+pub struct AdaptiveRate {
+    min_period: Duration,
+    max_period: Duration,
+    factor: f64,
+    period: Duration,
+    last_export: Option<Export>,
+}
+
+impl AdaptiveRate {
+    /// Creates a policy whose period doubles at each round with a stable export.
+    pub fn new(min_period: Duration, max_period: Duration) -> Self {
+        AdaptiveRate {
+            min_period,
+            max_period,
+            factor: 2.0,
+            period: min_period,
+            last_export: None,
+        }
+    }
+
+    /// Sets the factor by which the period grows at each round with a stable export.
+    ///
+    /// # Panics
+    /// Panics if `factor` is not a finite number of at least 1, since the period would shrink.
+    pub fn with_factor(mut self, factor: f64) -> Self {
+        assert!(
+            factor.is_finite() && factor >= 1.0,
+            "The factor must be a finite number of at least 1, but it is {factor}"
+        );
+        self.factor = factor;
+        self
+    }
+}
+
+impl Scheduler for AdaptiveRate {
+    fn next_round(&mut self, round: &RoundReport) -> Duration {
+        self.period = if self.last_export.as_ref() == Some(round.export) {
+            // A period too large to be represented is capped as well
+            Duration::try_from_secs_f64(self.period.as_secs_f64() * self.factor)
+                .map_or(self.max_period, |period| period.min(self.max_period))
+        } else {
+            self.min_period
+        };
+        self.last_export = Some(round.export.clone());
+        self.period.saturating_sub(round.duration)
+    }
+}
+
+/// This struct represents a policy executing a round as soon as a message of a neighbour
+/// arrives or a sensor changes, and at least every `max_delay` otherwise.
+///
+/// Two rounds are always at least `min_delay` apart, [DEFAULT_MIN_DELAY] unless it is set, so
+/// that the devices do not trigger each other at the speed of the network.
+pub struct EventTriggered {
+    max_delay: Duration,
+    min_delay: Duration,
+    poll_interval: Duration,
+    sensors_changed: Option<Box<dyn FnMut() -> bool>>,
+    last_round: Instant,
+    triggered: bool,
+}
+
+impl EventTriggered {
+    /// Creates a policy triggered by the messages of the neighbours only.
+    pub fn new(max_delay: Duration) -> Self {
+        EventTriggered {
+            max_delay,
+            min_delay: DEFAULT_MIN_DELAY,
+            poll_interval: Duration::from_millis(10),
+            sensors_changed: None,
+            last_round: Instant::now(),
+            triggered: false,
+        }
+    }
+
+    /// Sets the shortest time between the start of two rounds.
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Triggers a round when `changed` returns `true`. It is called every `poll_interval`
+    /// while the device waits.
+    pub fn on_sensor_change(
+        mut self,
+        poll_interval: Duration,
+        changed: impl FnMut() -> bool + 'static,
+    ) -> Self {
+        self.poll_interval = poll_interval;
+        self.sensors_changed = Some(Box::new(changed));
+        self
+    }
+}
+
+impl Scheduler for EventTriggered {
+    fn next_round(&mut self, round: &RoundReport) -> Duration {
+        self.last_round = Instant::now()
+            .checked_sub(round.duration)
+            .unwrap_or_else(Instant::now);
+        self.triggered = false;
+        self.max_delay.saturating_sub(round.duration)
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(self.poll_interval)
+    }
+
+    fn wakes_on(&mut self, event: &Event) -> bool {
+        let changed = match event {
+            Event::Message { .. } => true,
+            Event::Poll => self
+                .sensors_changed
+                .as_mut()
+                .is_some_and(|changed| changed()),
+        };
+        self.triggered |= changed;
+        self.triggered && self.last_round.elapsed() >= self.min_delay
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rf_core::export;
+    use rf_core::export::Export;
+    use rf_core::path::Path;
+    use std::any::Any;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn report(duration: Duration, export: &Export) -> RoundReport<'_> {
+        RoundReport { duration, export }
+    }
+
+    #[test]
+    fn test_fixed_rate() {
+        let export = export!((Path::new(), 1));
+        let mut scheduler = FixedRate::new(Duration::from_secs(1));
+        let delay = scheduler.next_round(&report(Duration::from_millis(300), &export));
+        assert_eq!(delay, Duration::from_millis(700));
+        let delay = scheduler.next_round(&report(Duration::from_secs(2), &export));
+        assert_eq!(delay, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_jittered_rate() {
+        let export = export!((Path::new(), 1));
+        let mut scheduler = JitteredRate::new(Duration::from_secs(1), Duration::from_millis(100));
+        let delays: Vec<Duration> = (0..100)
+            .map(|_| scheduler.next_round(&report(Duration::ZERO, &export)))
+            .collect();
+        assert!(delays
+            .iter()
+            .all(|d| *d >= Duration::from_millis(900) && *d <= Duration::from_millis(1100)));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[test]
+    fn test_adaptive_rate() {
+        let stable = export!((Path::new(), 1));
+        let changed = export!((Path::new(), 2));
+        let mut scheduler =
+            AdaptiveRate::new(Duration::from_secs(1), Duration::from_secs(3)).with_factor(1.5);
+        let mut next = |export: &Export| scheduler.next_round(&report(Duration::ZERO, export));
+        assert_eq!(next(&stable), Duration::from_secs(1));
+        assert_eq!(next(&stable), Duration::from_millis(1500));
+        assert_eq!(next(&stable), Duration::from_millis(2250));
+        assert_eq!(next(&stable), Duration::from_secs(3));
+        assert_eq!(next(&changed), Duration::from_secs(1));
+
+        // A huge factor reaches the longest period at once
+        let mut scheduler =
+            AdaptiveRate::new(Duration::from_secs(1), Duration::from_secs(3)).with_factor(1e300);
+        let mut next = |export: &Export| scheduler.next_round(&report(Duration::ZERO, export));
+        assert_eq!(next(&stable), Duration::from_secs(1));
+        assert_eq!(next(&stable), Duration::from_secs(3));
+        assert_eq!(next(&stable), Duration::from_secs(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_adaptive_rate_rejects_shrinking_factors() {
+        AdaptiveRate::new(Duration::from_secs(1), Duration::from_secs(3)).with_factor(0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_adaptive_rate_rejects_nan_factors() {
+        AdaptiveRate::new(Duration::from_secs(1), Duration::from_secs(3)).with_factor(f64::NAN);
+    }
+
+    #[test]
+    fn test_event_triggered() {
+        let export = export!((Path::new(), 1));
+        let sensor = Rc::new(Cell::new(false));
+        let changed = sensor.clone();
+        let mut scheduler = EventTriggered::new(Duration::from_secs(5))
+            .with_min_delay(Duration::from_millis(20))
+            .on_sensor_change(Duration::from_millis(5), move || changed.replace(false));
+        assert_eq!(scheduler.poll_interval(), Some(Duration::from_millis(5)));
+
+        let delay = scheduler.next_round(&report(Duration::ZERO, &export));
+        assert_eq!(delay, Duration::from_secs(5));
+        assert!(!scheduler.wakes_on(&Event::Poll));
+        // Too close to the previous round, the message is remembered
+        assert!(!scheduler.wakes_on(&Event::Message { source: 2 }));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(scheduler.wakes_on(&Event::Poll));
+
+        scheduler.next_round(&report(Duration::from_millis(20), &export));
+        assert!(!scheduler.wakes_on(&Event::Poll));
+        sensor.set(true);
+        assert!(scheduler.wakes_on(&Event::Poll));
+    }
+
+    #[test]
+    fn test_event_triggered_default_min_delay() {
+        let export = export!((Path::new(), 1));
+        let mut scheduler = EventTriggered::new(Duration::from_secs(5));
+        scheduler.next_round(&report(Duration::ZERO, &export));
+        // A neighbour sending messages continuously cannot trigger the rounds back to back
+        assert!(!scheduler.wakes_on(&Event::Message { source: 2 }));
+        std::thread::sleep(DEFAULT_MIN_DELAY);
+        assert!(scheduler.wakes_on(&Event::Message { source: 2 }));
+    }
+}
@@ -1,8 +1,15 @@
-use rf_distributed::time::Time;
+use async_trait::async_trait;
+use rf_distributed::time::{asynchronous, Time};
 use std::time::Duration;
 
 pub struct TimeImpl;
 
+impl Default for TimeImpl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TimeImpl {
     pub fn new() -> Self {
         TimeImpl
@@ -14,3 +21,25 @@ impl Time for TimeImpl {
         std::thread::sleep(duration);
     }
 }
+
+/// A [Time] that sleeps through the tokio timer, without blocking the thread it runs on.
+pub struct AsyncTimeImpl;
+
+impl Default for AsyncTimeImpl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsyncTimeImpl {
+    pub fn new() -> Self {
+        AsyncTimeImpl
+    }
+}
+
+#[async_trait]
+impl asynchronous::Time for AsyncTimeImpl {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
@@ -0,0 +1,224 @@
+use bytes::Bytes;
+use rf_core::context::{Context, NbrSensors};
+use rf_core::export::Export;
+use rf_core::sensor_id::sensor;
+use rf_distributed::discovery::nbr_sensors_setup::NbrSensorSetup;
+use rf_distributed::discovery::Discovery;
+use rf_distributed::network::sync::Network;
+use rf_distributed::network::{NetworkResult, NetworkUpdate};
+use rf_distributed::platform::sync::RuFiPlatform;
+use rf_distributed_impl::mailbox::{MailboxFactory, MemoryLessMailbox, RetainingMailbox};
+use rf_distributed_impl::network::local::{LocalNetwork, NetworkHub};
+use rf_distributed_impl::scheduler::FixedRate;
+use rf_distributed_impl::time::TimeImpl;
+use rufi_gradient::gradient;
+use std::any::Any;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+struct HubDiscovery {
+    id: i32,
+    hub: NetworkHub,
+}
+
+impl Discovery for HubDiscovery {
+    fn discover_neighbors(&self) -> Vec<i32> {
+        self.hub.neighbours(self.id)
+    }
+}
+
+struct NoSetup;
+
+impl NbrSensorSetup for NoSetup {
+    fn nbr_sensor_setup(&self, _nbrs: Vec<i32>) -> NbrSensors {
+        Default::default()
+    }
+}
+
+type Platform<N = LocalNetwork> = RuFiPlatform<
+    RetainingMailbox<MemoryLessMailbox>,
+    N,
+    HubDiscovery,
+    NoSetup,
+    TimeImpl,
+    FixedRate,
+    fn(&Export),
+>;
+
+fn platform(id: i32, source: i32, hub: &NetworkHub) -> Platform {
+    platform_on(id, source, hub, hub.network(id))
+}
+
+fn platform_on<N: Network>(id: i32, source: i32, hub: &NetworkHub, network: N) -> Platform<N> {
+    let local_sensors = HashMap::from([(
+        sensor("source"),
+        Rc::new(Box::new(id == source) as Box<dyn Any>),
+    )]);
+    let context = Context::new(id, local_sensors, Default::default(), Default::default());
+    let discovery = HubDiscovery {
+        id,
+        hub: hub.clone(),
+    };
+    // The exports of the devices that are no longer linked are discarded
+    RuFiPlatform::new(
+        RetainingMailbox::new(MailboxFactory::memory_less()).drop_undiscovered(),
+        network,
+        context,
+        discovery,
+        NoSetup,
+        TimeImpl::new(),
+        FixedRate::new(Duration::from_secs(1)),
+        vec![],
+    )
+}
+
+/// Executes the gradient on every device in order of id, for the given number of sweeps.
+///
+/// # Returns
+/// The distance of each device from the source in the last sweep.
+fn run_gradient(platforms: &mut HashMap<i32, Platform>, sweeps: usize) -> HashMap<i32, f64> {
+    let mut ids: Vec<i32> = platforms.keys().cloned().collect();
+    ids.sort();
+    let mut results = HashMap::new();
+    for _ in 0..sweeps {
+        for id in ids.iter() {
+            let export = platforms
+                .get_mut(id)
+                .unwrap()
+                .single_cycle(gradient)
+                .unwrap();
+            results.insert(*id, export.root::<f64>());
+        }
+    }
+    results
+}
+
+fn line(hub: &NetworkHub, source: i32) -> HashMap<i32, Platform> {
+    // Topology: [1] -- [2] -- [3] -- [4] -- [5]
+    for id in 1..5 {
+        hub.link(id, id + 1);
+    }
+    (1..=5).map(|id| (id, platform(id, source, hub))).collect()
+}
+
+#[test]
+fn test_gradient_on_line() {
+    let hub = NetworkHub::new();
+    let mut platforms = line(&hub, 2);
+    let results = run_gradient(&mut platforms, 10);
+    assert_eq!(
+        results,
+        HashMap::from([(1, 1.0), (2, 0.0), (3, 1.0), (4, 2.0), (5, 3.0)])
+    );
+}
+
+#[test]
+fn test_gradient_adapts_to_links() {
+    let hub = NetworkHub::new();
+    let mut platforms = line(&hub, 1);
+    let line_distances = HashMap::from([(1, 0.0), (2, 1.0), (3, 2.0), (4, 3.0), (5, 4.0)]);
+    assert_eq!(run_gradient(&mut platforms, 10), line_distances);
+
+    // Topology: [2] -- [1] -- [3] -- [4] -- [5]
+    hub.unlink(2, 3);
+    hub.link(1, 3);
+    assert_eq!(
+        run_gradient(&mut platforms, 10),
+        HashMap::from([(1, 0.0), (2, 1.0), (3, 1.0), (4, 2.0), (5, 3.0)])
+    );
+
+    // Back to the line
+    hub.unlink(1, 3);
+    hub.link(2, 3);
+    assert_eq!(run_gradient(&mut platforms, 10), line_distances);
+}
+
+#[test]
+fn test_undecodable_messages_are_skipped() {
+    let hub = NetworkHub::new();
+    let mut platforms = line(&hub, 2);
+    // Device 6 is not running the program, and only sends garbage to device 3
+    hub.link(3, 6);
+    let mut garbage = hub.network(6);
+    garbage.send(6, Bytes::from("not a message")).unwrap();
+    let results = run_gradient(&mut platforms, 10);
+    assert_eq!(
+        results,
+        HashMap::from([(1, 1.0), (2, 0.0), (3, 1.0), (4, 2.0), (5, 3.0)])
+    );
+}
+
+/// A network on which a neighbour sends garbage faster than it can be received.
+struct FloodedNetwork(LocalNetwork);
+
+impl Network for FloodedNetwork {
+    fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.0.send(source, msg)
+    }
+
+    fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        Ok(NetworkUpdate::Update {
+            msg: Bytes::from("not a message"),
+        })
+    }
+}
+
+#[test]
+fn test_flooded_device_keeps_running() {
+    let hub = NetworkHub::new();
+    hub.link(1, 2);
+    let mut flooded = platform_on(1, 1, &hub, FloodedNetwork(hub.network(1)));
+    for _ in 0..3 {
+        let export = flooded.single_cycle(gradient).unwrap();
+        assert_eq!(export.root::<f64>(), 0.0);
+    }
+}
+
+/// A network that fails a number of times before receiving the messages.
+struct FailingNetwork(LocalNetwork, usize);
+
+impl Network for FailingNetwork {
+    fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()> {
+        self.0.send(source, msg)
+    }
+
+    fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.0.receive()
+    }
+
+    fn try_receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        if self.1 > 0 {
+            self.1 -= 1;
+            return Err("The network is down".into());
+        }
+        self.0.try_receive()
+    }
+}
+
+#[test]
+fn test_network_errors_do_not_stop_the_device() {
+    let hub = NetworkHub::new();
+    hub.link(1, 2);
+    let mut failing = platform_on(1, 2, &hub, FailingNetwork(hub.network(1), 3));
+    let mut source = platform(2, 2, &hub);
+    let mut distance = f64::INFINITY;
+    for _ in 0..5 {
+        source.single_cycle(gradient).unwrap();
+        distance = failing.single_cycle(gradient).unwrap().root::<f64>();
+    }
+    assert_eq!(distance, 1.0);
+}
+
+#[test]
+fn test_gradient_on_star() {
+    // Topology: [2], [3], [4] and [5] linked to [1]
+    let hub = NetworkHub::with_adjacency(HashMap::from([(1, vec![2, 3, 4, 5])]));
+    let mut platforms: HashMap<i32, Platform> =
+        (1..=5).map(|id| (id, platform(id, 5, &hub))).collect();
+    let results = run_gradient(&mut platforms, 10);
+    assert_eq!(
+        results,
+        HashMap::from([(1, 1.0), (2, 2.0), (3, 2.0), (4, 2.0), (5, 0.0)])
+    );
+}
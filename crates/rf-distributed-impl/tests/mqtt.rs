@@ -0,0 +1,208 @@
+mod utils;
+
+use bytes::Bytes;
+use rf_distributed::network::asynchronous::Network;
+use rf_distributed::network::NetworkUpdate;
+use rf_distributed_impl::network::{AsyncMQTTNetwork, MqttNetworkConfig};
+use rumqttc::{MqttOptions, Publish, QoS};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::time::{sleep, timeout};
+use utils::{start_broker, start_recording_broker, Published};
+
+async fn connect(broker: SocketAddr, id: i32, nbrs: Vec<i32>) -> AsyncMQTTNetwork {
+    let options = MqttOptions::new(
+        format!("device#{id}"),
+        broker.ip().to_string(),
+        broker.port(),
+    );
+    AsyncMQTTNetwork::new(options, nbrs, 10).await.unwrap()
+}
+
+fn config(broker: SocketAddr, id: i32) -> MqttNetworkConfig {
+    let options = MqttOptions::new(
+        format!("device#{id}"),
+        broker.ip().to_string(),
+        broker.port(),
+    );
+    MqttNetworkConfig::new(options)
+}
+
+/// Waits for the broker to record the publication of the given message.
+async fn published(published: &Published, msg: &str) -> Publish {
+    for _ in 0..100 {
+        let found = published
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|publish| publish.payload == msg)
+            .cloned();
+        if let Some(publish) = found {
+            return publish;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{msg} has not been published");
+}
+
+/// Receives a message from the network, if one arrives within 200 milliseconds.
+async fn receive(network: &mut AsyncMQTTNetwork) -> Option<Bytes> {
+    match timeout(Duration::from_millis(200), network.receive()).await {
+        Ok(Ok(NetworkUpdate::Update { msg })) => Some(msg),
+        _ => None,
+    }
+}
+
+/// Sends a message until the receiver gets it, since the subscription of the receiver may not
+/// have reached the broker yet.
+async fn deliver(sender: &mut AsyncMQTTNetwork, id: i32, receiver: &mut AsyncMQTTNetwork) {
+    let msg = Bytes::from(format!("export of {id}"));
+    for _ in 0..25 {
+        sender.send(id, msg.clone()).await.unwrap();
+        if let Some(received) = receive(receiver).await {
+            assert_eq!(received, msg);
+            return;
+        }
+    }
+    panic!("The message of {id} has not been delivered");
+}
+
+#[tokio::test]
+async fn test_send_and_receive() {
+    let broker = start_broker().await;
+    let mut network_1 = connect(broker, 1, vec![2]).await;
+    let mut network_2 = connect(broker, 2, vec![1]).await;
+
+    deliver(&mut network_1, 1, &mut network_2).await;
+    deliver(&mut network_2, 2, &mut network_1).await;
+}
+
+#[tokio::test]
+async fn test_subscribe() {
+    let broker = start_broker().await;
+    let mut network_1 = connect(broker, 1, vec![]).await;
+    let mut network_2 = connect(broker, 2, vec![]).await;
+
+    network_1.send(2, Bytes::from("export of 2")).await.unwrap();
+    assert_eq!(receive(&mut network_2).await, None);
+
+    network_2.subscribe(1).await.unwrap();
+    deliver(&mut network_1, 1, &mut network_2).await;
+}
+
+#[tokio::test]
+async fn test_unsubscribe() {
+    let broker = start_broker().await;
+    let mut network_1 = connect(broker, 1, vec![]).await;
+    let mut network_2 = connect(broker, 2, vec![1]).await;
+    deliver(&mut network_1, 1, &mut network_2).await;
+
+    network_2.unsubscribe(1).await.unwrap();
+    // Discard the messages published before the broker processed the unsubscription
+    while receive(&mut network_2).await.is_some() {}
+    network_1.send(1, Bytes::from("export of 1")).await.unwrap();
+    assert_eq!(receive(&mut network_2).await, None);
+}
+
+#[tokio::test]
+async fn test_namespaces() {
+    let broker = start_broker().await;
+    let connect_to = |id: i32, namespace: &str| {
+        let options = MqttOptions::new(
+            format!("{namespace}#{id}"),
+            broker.ip().to_string(),
+            broker.port(),
+        );
+        let config = MqttNetworkConfig::new(options).with_prefix(namespace, "gradient");
+        AsyncMQTTNetwork::with_config(config, vec![1], 10)
+    };
+    let mut network_a = connect_to(1, "building-a").await.unwrap();
+    let mut network_b = connect_to(2, "building-b").await.unwrap();
+    let mut receiver_a = connect_to(3, "building-a").await.unwrap();
+
+    deliver(&mut network_a, 1, &mut receiver_a).await;
+    // Both namespaces contain a device 1, but only the exports of the same namespace arrive
+    network_b.send(1, Bytes::from("export of 1")).await.unwrap();
+    assert_eq!(receive(&mut receiver_a).await, None);
+}
+
+#[tokio::test]
+async fn test_drop_closes_the_connection() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let network = connect(listener.local_addr().unwrap(), 1, vec![]).await;
+    let (mut stream, _) = listener.accept().await.unwrap();
+
+    drop(network);
+    let mut buffer = vec![0; 1024];
+    let closed = timeout(Duration::from_secs(1), async {
+        while stream.read(&mut buffer).await.is_ok_and(|len| len > 0) {}
+    });
+    assert!(closed.await.is_ok());
+}
+
+#[tokio::test]
+async fn test_retained_exports() {
+    let (broker, log) = start_recording_broker().await;
+    let config_1 = config(broker, 1).with_retained_exports(true);
+    let mut network_1 = AsyncMQTTNetwork::with_config(config_1, vec![], 10)
+        .await
+        .unwrap();
+    network_1.send(1, Bytes::from("export of 1")).await.unwrap();
+    assert!(published(&log, "export of 1").await.retain);
+
+    // A neighbour that subscribes later receives the export without waiting for the next one
+    let mut network_2 = connect(broker, 2, vec![1]).await;
+    assert_eq!(
+        receive(&mut network_2).await,
+        Some(Bytes::from("export of 1"))
+    );
+}
+
+#[tokio::test]
+async fn test_at_least_once() {
+    let (broker, log) = start_recording_broker().await;
+    let connect_with_qos = |id: i32| {
+        let config = config(broker, id).with_qos(QoS::AtLeastOnce, QoS::AtLeastOnce);
+        AsyncMQTTNetwork::with_config(config, vec![1], 10)
+    };
+    let mut network_1 = connect_with_qos(1).await.unwrap();
+    let mut network_2 = connect_with_qos(2).await.unwrap();
+    deliver(&mut network_1, 1, &mut network_2).await;
+    while receive(&mut network_2).await.is_some() {}
+
+    // More messages than the client keeps in flight are only sent if the broker acknowledges them
+    let msgs: Vec<Bytes> = (0..150)
+        .map(|i| Bytes::from(format!("export {i} of 1")))
+        .collect();
+    for msg in msgs.iter() {
+        let sent = timeout(Duration::from_secs(1), network_1.send(1, msg.clone())).await;
+        assert!(sent.is_ok_and(|sent| sent.is_ok()));
+    }
+    for msg in msgs {
+        assert_eq!(receive(&mut network_2).await, Some(msg));
+    }
+    assert_eq!(published(&log, "export 0 of 1").await.qos, QoS::AtLeastOnce);
+}
+
+#[tokio::test]
+async fn test_last_will() {
+    let (broker, log) = start_recording_broker().await;
+    // Device 1 announces its departure on its own topic, so that its neighbours are notified
+    let config_1 = config(broker, 1);
+    let config_1 = config_1
+        .clone()
+        .with_last_will(config_1.topic(1), "departed")
+        .with_qos(QoS::AtLeastOnce, QoS::AtLeastOnce);
+    let mut network_1 = AsyncMQTTNetwork::with_config(config_1, vec![], 10)
+        .await
+        .unwrap();
+    let mut network_2 = connect(broker, 2, vec![1]).await;
+    deliver(&mut network_1, 1, &mut network_2).await;
+    while receive(&mut network_2).await.is_some() {}
+
+    // Device 1 disconnects without notice
+    drop(network_1);
+    assert_eq!(receive(&mut network_2).await, Some(Bytes::from("departed")));
+    assert_eq!(published(&log, "departed").await.qos, QoS::AtLeastOnce);
+}
@@ -0,0 +1,202 @@
+use bytes::Bytes;
+use rf_distributed::discovery::{AddressDiscovery, Discovery};
+use rf_distributed::network::sync::Network;
+use rf_distributed::network::NetworkUpdate;
+use rf_distributed_impl::network::tcp::TcpNetwork;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Registry = Arc<Mutex<HashMap<i32, SocketAddr>>>;
+
+/// Discovers the registered devices whose ids differ by at most one from the id of the device.
+struct LineDiscovery {
+    id: i32,
+    registry: Registry,
+}
+
+impl Discovery for LineDiscovery {
+    fn discover_neighbors(&self) -> Vec<i32> {
+        let registry = self.registry.lock().unwrap();
+        let mut nbrs: Vec<i32> = registry
+            .keys()
+            .filter(|nbr| (**nbr - self.id).abs() <= 1)
+            .cloned()
+            .collect();
+        nbrs.sort();
+        nbrs
+    }
+}
+
+impl AddressDiscovery for LineDiscovery {
+    fn address_of(&self, nbr: i32) -> Option<SocketAddr> {
+        self.registry.lock().unwrap().get(&nbr).cloned()
+    }
+}
+
+fn start(id: i32, address: &str, registry: &Registry) -> TcpNetwork<LineDiscovery> {
+    let discovery = LineDiscovery {
+        id,
+        registry: registry.clone(),
+    };
+    let network = TcpNetwork::new(address.parse().unwrap(), discovery)
+        .unwrap()
+        .with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+    registry.lock().unwrap().insert(id, network.local_addr());
+    network
+}
+
+/// Subscribes the network to the given neighbours, as the platform does once it discovers them.
+fn subscribe(network: &mut TcpNetwork<LineDiscovery>, nbrs: &[i32]) {
+    for nbr in nbrs {
+        network.subscribe(*nbr).unwrap();
+    }
+}
+
+fn payload(network: &mut TcpNetwork<LineDiscovery>) -> Bytes {
+    match network.receive().unwrap() {
+        NetworkUpdate::Update { msg } => msg,
+        _ => panic!("No message received"),
+    }
+}
+
+#[test]
+fn test_send_to_neighbours() {
+    // Topology: [1] -- [2] -- [3]
+    let registry = Registry::default();
+    let mut nodes: HashMap<i32, _> = (1..=3)
+        .map(|id| (id, start(id, "127.0.0.1:0", &registry)))
+        .collect();
+    for id in 1..=3 {
+        let nbrs: Vec<i32> = (id - 1..=id + 1)
+            .filter(|nbr| (1..=3).contains(nbr))
+            .collect();
+        subscribe(nodes.get_mut(&id).unwrap(), &nbrs);
+    }
+
+    let msg = Bytes::from("export of 2");
+    nodes.get_mut(&2).unwrap().send(2, msg.clone()).unwrap();
+    for id in 1..=3 {
+        assert_eq!(payload(nodes.get_mut(&id).unwrap()), msg);
+    }
+
+    // Node 3 is not a neighbour of node 1, so only nodes 1 and 2 receive the message
+    let msg = Bytes::from("export of 1");
+    nodes.get_mut(&1).unwrap().send(1, msg.clone()).unwrap();
+    let msg_3 = Bytes::from("export of 3");
+    nodes.get_mut(&3).unwrap().send(3, msg_3.clone()).unwrap();
+    assert_eq!(payload(nodes.get_mut(&1).unwrap()), msg);
+    assert_eq!(payload(nodes.get_mut(&3).unwrap()), msg_3);
+}
+
+#[test]
+fn test_large_messages() {
+    let registry = Registry::default();
+    let mut node_1 = start(1, "127.0.0.1:0", &registry);
+    let mut node_2 = start(2, "127.0.0.1:0", &registry);
+    subscribe(&mut node_1, &[2]);
+
+    // Frames larger than the buffers of the sockets are reassembled
+    let msg = Bytes::from(vec![42; 1 << 20]);
+    let receiver = thread::spawn(move || payload(&mut node_2));
+    node_1.send(1, msg.clone()).unwrap();
+    assert_eq!(receiver.join().unwrap(), msg);
+}
+
+#[test]
+fn test_reconnect() {
+    let registry = Registry::default();
+    let mut node_1 = start(1, "127.0.0.1:0", &registry);
+    let node_2 = start(2, "127.0.0.1:0", &registry);
+    let address = node_2.local_addr().to_string();
+    subscribe(&mut node_1, &[2]);
+    node_1.send(1, Bytes::from("export of 1")).unwrap();
+    drop(node_2);
+
+    // Node 2 restarts on the same address, and node 1 connects to it again
+    let mut node_2 = start(2, &address, &registry);
+    let (sender, received) = channel();
+    thread::spawn(move || sender.send(payload(&mut node_2)));
+    let msg = Bytes::from("export of 1 after the restart");
+    for _ in 0..100 {
+        let _ = node_1.send(1, msg.clone());
+        if let Ok(received) = received.recv_timeout(Duration::from_millis(20)) {
+            assert_eq!(received, msg);
+            return;
+        }
+    }
+    panic!("Node 1 has not reconnected to node 2");
+}
+
+#[test]
+fn test_neighbour_moves() {
+    let registry = Registry::default();
+    let mut node_1 = start(1, "127.0.0.1:0", &registry);
+    // The address of node 2 is not known until it starts
+    assert!(node_1.subscribe(2).is_err());
+    let node_2 = start(2, "127.0.0.1:0", &registry);
+    subscribe(&mut node_1, &[2]);
+    node_1.send(1, Bytes::from("export of 1")).unwrap();
+    drop(node_2);
+
+    // Node 2 restarts on another address, which node 1 looks up once node 2 cannot be reached
+    let mut node_2 = start(2, "127.0.0.1:0", &registry);
+    let (sender, received) = channel();
+    thread::spawn(move || sender.send(payload(&mut node_2)));
+    let msg = Bytes::from("export of 1 after the move");
+    for _ in 0..100 {
+        let _ = node_1.send(1, msg.clone());
+        if let Ok(received) = received.recv_timeout(Duration::from_millis(20)) {
+            assert_eq!(received, msg);
+            return;
+        }
+    }
+    panic!("Node 1 has not reached node 2 at its new address");
+}
+
+#[test]
+fn test_stalled_neighbour() {
+    let registry = Registry::default();
+    let mut node_1 = start(1, "127.0.0.1:0", &registry);
+    // Node 2 accepts the connection, but never reads from it
+    let node_2 = TcpListener::bind("127.0.0.1:0").unwrap();
+    registry
+        .lock()
+        .unwrap()
+        .insert(2, node_2.local_addr().unwrap());
+    subscribe(&mut node_1, &[2]);
+
+    let start = Instant::now();
+    let msg = Bytes::from(vec![42; 8 << 20]);
+    let results: Vec<_> = (0..3).map(|_| node_1.send(1, msg.clone())).collect();
+    assert!(results.iter().any(|result| result.is_err()));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_drop_closes_inbound_connections() {
+    let registry = Registry::default();
+    let mut node = start(1, "127.0.0.1:0", &registry);
+    let mut peer = TcpStream::connect(node.local_addr()).unwrap();
+    let msg = b"export of 2";
+    peer.write_all(&(msg.len() as u32).to_be_bytes()).unwrap();
+    peer.write_all(msg).unwrap();
+    assert_eq!(payload(&mut node), Bytes::from_static(msg));
+
+    drop(node);
+    peer.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(peer.read(&mut [0; 1]).unwrap(), 0);
+}
+
+#[test]
+fn test_drop_releases_every_interface() {
+    let registry = Registry::default();
+    let node = start(1, "0.0.0.0:0", &registry);
+    let port = node.local_addr().port();
+    drop(node);
+    assert!(TcpListener::bind(("0.0.0.0", port)).is_ok());
+}
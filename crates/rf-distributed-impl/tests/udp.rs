@@ -0,0 +1,236 @@
+use bytes::Bytes;
+use rf_core::context::{Context, NbrSensors};
+use rf_core::export::Export;
+use rf_core::lang::rep;
+use rf_core::vm::round_vm::RoundVM;
+use rf_distributed::discovery::nbr_sensors_setup::NbrSensorSetup;
+use rf_distributed::discovery::Discovery;
+use rf_distributed::network::{asynchronous, sync, NetworkResult, NetworkUpdate};
+use rf_distributed::platform::sync::RuFiPlatform;
+use rf_distributed_impl::mailbox::MailboxFactory;
+use rf_distributed_impl::network::udp::{AsyncUdpNetwork, UdpConfig, UdpMode, UdpNetwork};
+use rf_distributed_impl::scheduler::FixedRate;
+use rf_distributed_impl::time::TimeImpl;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::time::timeout;
+
+const LOCALHOST: Ipv4Addr = Ipv4Addr::LOCALHOST;
+
+fn payload(update: NetworkResult<NetworkUpdate>) -> Bytes {
+    match update.unwrap() {
+        NetworkUpdate::Update { msg } => msg,
+        _ => panic!("No message received"),
+    }
+}
+
+/// Receives a message from the network, if one arrives within 200 milliseconds.
+async fn receive(network: &mut AsyncUdpNetwork) -> Option<Bytes> {
+    timeout(
+        Duration::from_millis(200),
+        asynchronous::Network::receive(network),
+    )
+    .await
+    .ok()
+    .map(payload)
+}
+
+#[test]
+fn test_broadcast() {
+    let config = UdpConfig::new(47301, UdpMode::Broadcast(Ipv4Addr::new(127, 255, 255, 255)));
+    let mut nodes: Vec<UdpNetwork> = (0..3)
+        .map(|_| UdpNetwork::new(config.clone()).unwrap())
+        .collect();
+    for node in nodes.iter_mut().skip(1) {
+        sync::Network::subscribe(node, 1).unwrap();
+    }
+
+    let msg = Bytes::from("export of 1");
+    sync::Network::send(&mut nodes[0], 1, msg.clone()).unwrap();
+    for node in nodes.iter_mut() {
+        assert_eq!(payload(sync::Network::receive(node)), msg);
+    }
+}
+
+#[tokio::test]
+async fn test_multicast() {
+    let config = UdpConfig::new(47302, UdpMode::Multicast(Ipv4Addr::new(239, 255, 0, 1)))
+        .with_interface(LOCALHOST);
+    let mut nodes: Vec<AsyncUdpNetwork> = (0..3)
+        .map(|_| AsyncUdpNetwork::new(config.clone()).unwrap())
+        .collect();
+    for node in nodes.iter_mut() {
+        asynchronous::Network::subscribe(node, 2).await.unwrap();
+    }
+
+    let msg = Bytes::from("export of 2");
+    asynchronous::Network::send(&mut nodes[1], 2, msg.clone())
+        .await
+        .unwrap();
+    for node in nodes.iter_mut() {
+        assert_eq!(receive(node).await, Some(msg.clone()));
+    }
+
+    // The datagrams of the devices that are not subscribed to are discarded
+    asynchronous::Network::unsubscribe(&mut nodes[0], 2)
+        .await
+        .unwrap();
+    asynchronous::Network::send(&mut nodes[1], 2, msg.clone())
+        .await
+        .unwrap();
+    assert_eq!(receive(&mut nodes[0]).await, None);
+    assert_eq!(receive(&mut nodes[2]).await, Some(msg));
+}
+
+#[tokio::test]
+async fn test_unicast() {
+    // Topology: [1] -- [2] -- [3]
+    let address = |id: i32| SocketAddr::from((LOCALHOST, 47302 + id as u16));
+    let mut nodes: HashMap<i32, AsyncUdpNetwork> = (1..=3)
+        .map(|id| {
+            let nbrs = [id - 1, id + 1]
+                .into_iter()
+                .filter(|nbr| (1..=3).contains(nbr))
+                .map(|nbr| (nbr, address(nbr)))
+                .collect();
+            let config = UdpConfig::new(address(id).port(), UdpMode::Unicast(nbrs))
+                .with_interface(LOCALHOST);
+            (id, AsyncUdpNetwork::new(config).unwrap())
+        })
+        .collect();
+    assert_eq!(nodes[&2].local_addr().unwrap(), address(2));
+    for (id, node) in nodes.iter_mut() {
+        asynchronous::Network::subscribe(node, id - 1)
+            .await
+            .unwrap();
+        asynchronous::Network::subscribe(node, id + 1)
+            .await
+            .unwrap();
+    }
+
+    // The device receives its own export as well
+    let msg = Bytes::from("export of 1");
+    let node_1 = nodes.get_mut(&1).unwrap();
+    asynchronous::Network::send(node_1, 1, msg.clone())
+        .await
+        .unwrap();
+    assert_eq!(receive(nodes.get_mut(&2).unwrap()).await, Some(msg.clone()));
+    assert_eq!(receive(nodes.get_mut(&3).unwrap()).await, None);
+    assert_eq!(receive(nodes.get_mut(&1).unwrap()).await, Some(msg));
+
+    let msg = Bytes::from("export of 2");
+    let node_2 = nodes.get_mut(&2).unwrap();
+    asynchronous::Network::send(node_2, 2, msg.clone())
+        .await
+        .unwrap();
+    assert_eq!(receive(nodes.get_mut(&1).unwrap()).await, Some(msg.clone()));
+    assert_eq!(receive(nodes.get_mut(&3).unwrap()).await, Some(msg));
+}
+
+#[test]
+fn test_sync_unicast() {
+    let receiver = UdpNetwork::new(
+        UdpConfig::new(0, UdpMode::Unicast(HashMap::new())).with_interface(LOCALHOST),
+    );
+    let mut receiver = receiver.unwrap();
+    let nbrs = HashMap::from([(2, receiver.local_addr().unwrap())]);
+    let mut sender =
+        UdpNetwork::new(UdpConfig::new(0, UdpMode::Unicast(nbrs)).with_interface(LOCALHOST))
+            .unwrap();
+    sync::Network::subscribe(&mut receiver, 1).unwrap();
+
+    let msg = Bytes::from("export of 1");
+    sync::Network::send(&mut sender, 1, msg.clone()).unwrap();
+    assert_eq!(payload(sync::Network::receive(&mut receiver)), msg);
+    assert_eq!(payload(sync::Network::receive(&mut sender)), msg);
+}
+
+#[test]
+fn test_receive_timeout() {
+    let receiver = UdpNetwork::new(
+        UdpConfig::new(0, UdpMode::Unicast(HashMap::new())).with_interface(LOCALHOST),
+    );
+    let mut receiver = receiver.unwrap();
+    let nbrs = HashMap::from([(2, receiver.local_addr().unwrap())]);
+    let mut sender =
+        UdpNetwork::new(UdpConfig::new(0, UdpMode::Unicast(nbrs)).with_interface(LOCALHOST))
+            .unwrap();
+    sync::Network::subscribe(&mut receiver, 1).unwrap();
+    let receive_timeout = sync::Network::receive_timeout;
+    assert!(matches!(
+        receive_timeout(&mut receiver, Duration::from_millis(20)),
+        Ok(NetworkUpdate::None)
+    ));
+
+    let msg = Bytes::from("export of 1");
+    sync::Network::send(&mut sender, 1, msg.clone()).unwrap();
+    let update = receive_timeout(&mut receiver, Duration::from_secs(1));
+    assert_eq!(payload(update), msg);
+    assert!(matches!(
+        sync::Network::try_receive(&mut receiver),
+        Ok(NetworkUpdate::None)
+    ));
+}
+
+/// Discovers the devices whose ids differ by one from the id of the device.
+struct LineDiscovery(i32);
+
+impl Discovery for LineDiscovery {
+    fn discover_neighbors(&self) -> Vec<i32> {
+        vec![self.0 - 1, self.0 + 1]
+    }
+}
+
+struct NoSetup;
+
+impl NbrSensorSetup for NoSetup {
+    fn nbr_sensor_setup(&self, _nbrs: Vec<i32>) -> NbrSensors {
+        Default::default()
+    }
+}
+
+fn count_rounds(vm: &mut RoundVM) -> i32 {
+    rep(vm, |_vm| 0, |_vm, rounds| rounds + 1)
+}
+
+#[test]
+fn test_unicast_platform_keeps_state() {
+    // Topology: [1] -- [2] -- [3]
+    let address = |id: i32| SocketAddr::from((LOCALHOST, 47310 + id as u16));
+    let mut platforms: Vec<_> = (1..=3)
+        .map(|id| {
+            let nbrs = [id - 1, id + 1]
+                .into_iter()
+                .filter(|nbr| (1..=3).contains(nbr))
+                .map(|nbr| (nbr, address(nbr)))
+                .collect();
+            let config = UdpConfig::new(address(id).port(), UdpMode::Unicast(nbrs))
+                .with_interface(LOCALHOST);
+            let context = Context::new(
+                id,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            );
+            RuFiPlatform::new(
+                MailboxFactory::memory_less(),
+                UdpNetwork::new(config).unwrap(),
+                context,
+                LineDiscovery(id),
+                NoSetup,
+                TimeImpl::new(),
+                FixedRate::new(Duration::from_secs(1)),
+                Vec::<fn(&Export)>::new(),
+            )
+        })
+        .collect();
+
+    // Every round starts from the export of the previous round of the device itself
+    for rounds in 1..=5 {
+        for platform in platforms.iter_mut() {
+            let export = platform.single_cycle(count_rounds).unwrap();
+            assert_eq!(export.root::<i32>(), rounds);
+        }
+    }
+}
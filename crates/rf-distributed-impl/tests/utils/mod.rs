@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+
+use bytes::BytesMut;
+use rumqttc::mqttbytes::Error;
+use rumqttc::{
+    read, ConnAck, ConnectReturnCode, LastWill, Packet, PingResp, PubAck, PubComp, PubRec, Publish,
+    QoS, SubAck, SubscribeReasonCode, UnsubAck,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+/// The clients subscribed to each topic, with the quality of service granted to each of them.
+type Subscriptions = HashMap<String, HashMap<usize, (UnboundedSender<Publish>, QoS)>>;
+
+/// The messages published on the broker, in order, including the last wills of the clients.
+pub type Published = Arc<Mutex<Vec<Publish>>>;
+
+#[derive(Clone, Default)]
+struct Broker {
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    retained: Arc<Mutex<HashMap<String, Publish>>>,
+    published: Published,
+}
+
+/// Starts a minimal MQTT broker on a random local port, which delivers the messages published on
+/// a topic to the clients subscribed to that exact topic.
+///
+/// The broker grants at most the quality of service [QoS::AtLeastOnce], retains the last message
+/// published with the retain flag on each topic, and publishes the last will of the clients that
+/// disconnect without notice.
+///
+/// # Returns
+/// The address of the broker.
+pub async fn start_broker() -> SocketAddr {
+    start_recording_broker().await.0
+}
+
+/// Starts a broker like [start_broker], which also records the messages published on it.
+///
+/// # Returns
+/// The address of the broker and the messages published on it.
+pub async fn start_recording_broker() -> (SocketAddr, Published) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let broker = Broker::default();
+    let published = broker.published.clone();
+    tokio::spawn(async move {
+        let mut next_client = 0;
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, next_client, broker.clone()));
+            next_client += 1;
+        }
+    });
+    (address, published)
+}
+
+/// The lower of two qualities of service.
+fn lower(a: QoS, b: QoS) -> QoS {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+/// Copies a message for a subscriber, which receives it with the granted quality of service at
+/// most.
+fn forward(publish: &Publish, granted: QoS, retain: bool) -> Publish {
+    let mut forwarded = Publish::new(publish.topic.clone(), lower(publish.qos, granted), vec![]);
+    forwarded.payload = publish.payload.clone();
+    forwarded.retain = retain;
+    forwarded
+}
+
+fn publish(broker: &Broker, publish: Publish) {
+    broker.published.lock().unwrap().push(publish.clone());
+    if publish.retain {
+        let mut retained = broker.retained.lock().unwrap();
+        if publish.payload.is_empty() {
+            retained.remove(&publish.topic);
+        } else {
+            retained.insert(publish.topic.clone(), publish.clone());
+        }
+    }
+    let subscriptions = broker.subscriptions.lock().unwrap();
+    for (subscriber, granted) in subscriptions
+        .get(&publish.topic)
+        .into_iter()
+        .flat_map(|c| c.values())
+    {
+        subscriber
+            .send(forward(&publish, *granted, false))
+            .unwrap_or(());
+    }
+}
+
+fn last_will(will: LastWill) -> Publish {
+    let mut publish = Publish::new(will.topic, will.qos, vec![]);
+    publish.payload = will.message;
+    publish.retain = will.retain;
+    publish
+}
+
+async fn serve(stream: TcpStream, client: usize, broker: Broker) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (outgoing, mut incoming) = unbounded_channel::<Publish>();
+    let (replies, mut pending_replies) = unbounded_channel::<BytesMut>();
+    tokio::spawn(async move {
+        let mut next_pkid: u16 = 0;
+        loop {
+            let mut buffer = BytesMut::new();
+            tokio::select! {
+                Some(mut publish) = incoming.recv() => {
+                    if publish.qos != QoS::AtMostOnce {
+                        next_pkid = next_pkid.checked_add(1).unwrap_or(1);
+                        publish.pkid = next_pkid;
+                    }
+                    publish.write(&mut buffer).unwrap();
+                }
+                Some(reply) = pending_replies.recv() => buffer = reply,
+                else => break,
+            }
+            if writer.write_all(&buffer).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut buffer = BytesMut::new();
+    let mut will = None;
+    loop {
+        let packet = match read(&mut buffer, 1 << 20) {
+            Ok(packet) => packet,
+            Err(Error::InsufficientBytes(_)) => match reader.read_buf(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => continue,
+            },
+            Err(_) => break,
+        };
+        let mut reply = BytesMut::new();
+        match packet {
+            Packet::Connect(connect) => {
+                will = connect.last_will;
+                ConnAck::new(ConnectReturnCode::Success, false)
+                    .write(&mut reply)
+                    .unwrap();
+            }
+            Packet::Subscribe(subscribe) => {
+                let mut subscriptions = broker.subscriptions.lock().unwrap();
+                let retained = broker.retained.lock().unwrap();
+                let mut codes = vec![];
+                for filter in subscribe.filters.iter() {
+                    let granted = lower(filter.qos, QoS::AtLeastOnce);
+                    subscriptions
+                        .entry(filter.path.clone())
+                        .or_default()
+                        .insert(client, (outgoing.clone(), granted));
+                    if let Some(publish) = retained.get(&filter.path) {
+                        outgoing.send(forward(publish, granted, true)).unwrap_or(());
+                    }
+                    codes.push(SubscribeReasonCode::Success(granted));
+                }
+                SubAck::new(subscribe.pkid, codes)
+                    .write(&mut reply)
+                    .unwrap();
+            }
+            Packet::Unsubscribe(unsubscribe) => {
+                let mut subscriptions = broker.subscriptions.lock().unwrap();
+                for topic in unsubscribe.topics.iter() {
+                    if let Some(clients) = subscriptions.get_mut(topic) {
+                        clients.remove(&client);
+                    }
+                }
+                UnsubAck::new(unsubscribe.pkid).write(&mut reply).unwrap();
+            }
+            Packet::Publish(received) => {
+                match received.qos {
+                    QoS::AtMostOnce => {}
+                    QoS::AtLeastOnce => {
+                        PubAck::new(received.pkid).write(&mut reply).unwrap();
+                    }
+                    QoS::ExactlyOnce => {
+                        PubRec::new(received.pkid).write(&mut reply).unwrap();
+                    }
+                }
+                publish(&broker, received);
+            }
+            Packet::PubRel(pubrel) => {
+                PubComp::new(pubrel.pkid).write(&mut reply).unwrap();
+            }
+            Packet::PingReq => {
+                PingResp.write(&mut reply).unwrap();
+            }
+            Packet::Disconnect => {
+                // A client that disconnects gracefully has no last will
+                will = None;
+                break;
+            }
+            _ => {}
+        }
+        if !reply.is_empty() && replies.send(reply).is_err() {
+            break;
+        }
+    }
+    for clients in broker.subscriptions.lock().unwrap().values_mut() {
+        clients.remove(&client);
+    }
+    if let Some(will) = will {
+        publish(&broker, last_will(will));
+    }
+}
@@ -1,5 +1,8 @@
+pub mod asynchronous;
 pub mod nbr_sensors_setup;
 
+use std::net::SocketAddr;
+
 /// This trait represents a discovery strategy for the platform
 pub trait Discovery {
     /// Discovers the neighbours of the device
@@ -8,3 +11,16 @@ pub trait Discovery {
     /// A vector containing the ids of the neighbours
     fn discover_neighbors(&self) -> Vec<i32>;
 }
+
+/// This trait represents a discovery strategy that also knows where the neighbours can be
+/// reached, for the networks that connect directly to them
+pub trait AddressDiscovery: Discovery {
+    /// Finds the address of a neighbour
+    ///
+    /// # Arguments
+    /// * `nbr` - The id of the neighbour
+    ///
+    /// # Returns
+    /// The address of the neighbour, if it is known
+    fn address_of(&self, nbr: i32) -> Option<SocketAddr>;
+}
@@ -0,0 +1,12 @@
+use async_trait::async_trait;
+
+/// This trait represents a discovery strategy for the platform that does not block the thread
+/// of the caller, e.g. because it queries a remote registry
+#[async_trait]
+pub trait Discovery {
+    /// Discovers the neighbours of the device
+    ///
+    /// # Returns
+    /// A vector containing the ids of the neighbours
+    async fn discover_neighbors(&self) -> Vec<i32>;
+}
@@ -3,5 +3,6 @@ pub mod mailbox;
 pub mod message;
 pub mod network;
 pub mod platform;
+pub mod scheduler;
 pub mod state;
 pub mod time;
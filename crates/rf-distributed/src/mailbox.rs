@@ -8,6 +8,11 @@ pub trait Mailbox {
     fn enqueue(&mut self, msg: Message);
     /// Returns the messages stored in the mailbox
     fn messages(&mut self) -> Messages;
+    /// Discards every message stored for a neighbour. By default, the messages are kept.
+    fn remove(&mut self, _nbr: i32) {}
+    /// Notifies the mailbox of the neighbours currently reported by the discovery, including the
+    /// device itself. By default, the notification is ignored.
+    fn update_neighbours(&mut self, _nbrs: &[i32]) {}
 }
 
 /// This type alias represent the messages stored in the mailbox
@@ -3,8 +3,11 @@ use async_trait::async_trait;
 use bytes::Bytes;
 
 /// This trait represent a network that will be used to send and receive messages
+///
+/// Since the futures of its methods are [Send], the network must be [Send] as well. This is
+/// required since version 0.7.0, so a network that is not [Send] can no longer implement it.
 #[async_trait]
-pub trait Network {
+pub trait Network: Send {
     /// Send a message to the network
     ///
     /// # Arguments
@@ -24,4 +27,22 @@ pub trait Network {
     /// * `Ok(NetworkUpdate)` - If a message has been received
     /// * `Err(e)` - If an error occurred
     async fn receive(&mut self) -> NetworkResult<NetworkUpdate>;
+    /// Starts receiving the messages of a neighbour. Networks that deliver the messages of
+    /// every neighbour anyway do nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `nbr` - The neighbour whose messages will be received
+    async fn subscribe(&mut self, _nbr: i32) -> NetworkResult<()> {
+        Ok(())
+    }
+    /// Stops receiving the messages of a neighbour. Networks that deliver the messages of
+    /// every neighbour anyway do nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `nbr` - The neighbour whose messages will no longer be received
+    async fn unsubscribe(&mut self, _nbr: i32) -> NetworkResult<()> {
+        Ok(())
+    }
 }
@@ -1,7 +1,31 @@
 use crate::network::{NetworkResult, NetworkUpdate};
 use bytes::Bytes;
+use std::time::Duration;
 
 pub trait Network {
     fn send(&mut self, source: i32, msg: Bytes) -> NetworkResult<()>;
     fn receive(&mut self) -> NetworkResult<NetworkUpdate>;
+    /// Receives a message, waiting for it at most `timeout`. If no message arrives in time,
+    /// [NetworkUpdate::None] is returned.
+    ///
+    /// The default implementation cannot stop waiting, so it ignores `timeout` and waits for a
+    /// message with [Network::receive]. Networks that can stop waiting should override it.
+    fn receive_timeout(&mut self, _timeout: Duration) -> NetworkResult<NetworkUpdate> {
+        self.receive()
+    }
+    /// Receives a message that has already arrived, without waiting. If no message is pending,
+    /// [NetworkUpdate::None] is returned.
+    fn try_receive(&mut self) -> NetworkResult<NetworkUpdate> {
+        self.receive_timeout(Duration::ZERO)
+    }
+    /// Starts receiving the messages of a neighbour. Networks that deliver the messages of
+    /// every neighbour anyway do nothing.
+    fn subscribe(&mut self, _nbr: i32) -> NetworkResult<()> {
+        Ok(())
+    }
+    /// Stops receiving the messages of a neighbour. Networks that deliver the messages of
+    /// every neighbour anyway do nothing.
+    fn unsubscribe(&mut self, _nbr: i32) -> NetworkResult<()> {
+        Ok(())
+    }
 }
@@ -1,5 +1,129 @@
 use crate::discovery::nbr_sensors_setup::NbrSensorSetup;
-use crate::discovery::Discovery;
-use crate::mailbox::Mailbox;
+use crate::mailbox::{AsStates, Mailbox};
+use crate::message::Message;
+use crate::network::NetworkUpdate;
+use bytes::Bytes;
+use rf_core::context::Context;
+use rf_core::export::Export;
+use rf_core::lang::execution::round;
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use std::any::Any;
+use std::error::Error;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::SystemTime;
 
+pub mod asynchronous;
 pub mod sync;
+
+/// Executes a round of the program with the neighbouring exports stored in the mailbox,
+/// providing the round-time sensors `delta_time` and `nbr_lag`.
+///
+/// # Returns
+///
+/// * `Export` - The export of the device
+pub(crate) fn execute_round<P, A, M, S>(
+    context: &Context,
+    mailbox: &mut M,
+    setup: &S,
+    last_round: &mut Option<SystemTime>,
+    program: P,
+) -> Export
+where
+    P: Fn(&mut RoundVM) -> A,
+    A: Clone + 'static + FromStr + Display,
+    M: Mailbox,
+    S: NbrSensorSetup,
+{
+    let messages = mailbox.messages();
+    let states = messages.as_states();
+
+    let now = SystemTime::now();
+    let elapsed = |since: SystemTime| now.duration_since(since).unwrap_or_default().as_secs_f64();
+    let mut local_sensors = context.local_sensors().clone();
+    let delta_time = last_round.map(elapsed).unwrap_or(0.0);
+    local_sensors.insert(
+        sensor("delta_time"),
+        Rc::new(Box::new(delta_time) as Box<dyn Any>),
+    );
+    *last_round = Some(now);
+    let mut nbr_sensors = setup.nbr_sensor_setup(states.keys().cloned().collect());
+    let nbr_lags = messages
+        .iter()
+        .map(|(id, msg)| {
+            (
+                *id,
+                Rc::new(Box::new(elapsed(msg.timestamp)) as Box<dyn Any>),
+            )
+        })
+        .collect();
+    nbr_sensors.insert(sensor("nbr_lag"), nbr_lags);
+    let context = Context::new(*context.self_id(), local_sensors, nbr_sensors, states);
+    let mut vm = RoundVM::new(context);
+    vm.new_export_stack();
+    let result = round(&mut vm, program);
+    let self_export: Export = vm.export_data().clone();
+    println!("OUTPUT: {}\nEXPORT: {}\n", result, self_export);
+    self_export
+}
+
+/// Compares the neighbours currently reported by the discovery with the ones the device is
+/// subscribed to.
+///
+/// # Returns
+///
+/// * `(Vec<i32>, Vec<i32>)` - The neighbours to subscribe to and the ones to unsubscribe from
+pub(crate) fn subscription_changes(subscribed: &[i32], nbrs: &[i32]) -> (Vec<i32>, Vec<i32>) {
+    let joined = nbrs
+        .iter()
+        .filter(|n| !subscribed.contains(n))
+        .cloned()
+        .collect();
+    let left = subscribed
+        .iter()
+        .filter(|n| !nbrs.contains(n))
+        .cloned()
+        .collect();
+    (joined, left)
+}
+
+/// Serializes the export of the device into a message ready to be sent through the network.
+pub(crate) fn encode(self_id: i32, export: Export) -> Option<Bytes> {
+    let msg = Message::new(self_id, export, SystemTime::now());
+    match serde_json::to_vec(&msg) {
+        Ok(msg_ser) => Some(Bytes::from(msg_ser)),
+        Err(_) => {
+            println!("Error while serializing the message");
+            None
+        }
+    }
+}
+
+/// Stores in the mailbox the message carried by an update received from the network.
+///
+/// # Returns
+///
+/// * `Result<Option<i32>, Box<dyn Error>>` - The source of the message, if one was received
+pub(crate) fn handle_update<M: Mailbox>(
+    mailbox: &mut M,
+    update: NetworkUpdate,
+) -> Result<Option<i32>, Box<dyn Error>> {
+    match update {
+        NetworkUpdate::Update { msg } => {
+            if let Ok(msg) = serde_json::from_slice::<Message>(&msg) {
+                let source = msg.source;
+                mailbox.enqueue(msg);
+                Ok(Some(source))
+            } else {
+                Err("Error deserializing the message".into())
+            }
+        }
+        NetworkUpdate::None => {
+            println!("No message received from the network");
+            Ok(None)
+        }
+        NetworkUpdate::Err { reason } => Err(reason.into()),
+    }
+}
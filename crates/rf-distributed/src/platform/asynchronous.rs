@@ -0,0 +1,533 @@
+use crate::discovery::asynchronous::Discovery;
+use crate::discovery::nbr_sensors_setup::NbrSensorSetup;
+use crate::mailbox::Mailbox;
+use crate::network::asynchronous::Network;
+use crate::network::NetworkUpdate;
+use crate::platform::{encode, execute_round, handle_update, subscription_changes};
+use crate::scheduler::{Event, RoundReport, Scheduler};
+use crate::time::asynchronous::Time;
+use rf_core::context::Context;
+use rf_core::export::Export;
+use rf_core::vm::round_vm::RoundVM;
+use std::error::Error;
+use std::fmt::Display;
+use std::pin::pin;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+/// This struct represents the platform on which the program is executed, without ever blocking
+/// the thread it runs on.
+///
+/// While the platform waits for the next round, as decided by a [Scheduler], it keeps receiving
+/// the neighbouring exports from the network. The platform is not [Send], since the context of
+/// the device is not, so many devices can share a runtime by running on the same thread, e.g.
+/// with `tokio::join!` or a `tokio::task::LocalSet`.
+pub struct RuFiPlatform<M, N, D, S, T, C, H>
+where
+    M: Mailbox,
+    N: Network,
+    D: Discovery,
+    S: NbrSensorSetup,
+    T: Time,
+    C: Scheduler,
+    H: Fn(&Export),
+{
+    mailbox: M,
+    network: N,
+    context: Context,
+    discovery: D,
+    discovered_nbrs: Vec<i32>,
+    nbr_sensor_setup: S,
+    time: T,
+    scheduler: C,
+    hooks: Vec<H>,
+    last_round: Option<SystemTime>,
+}
+
+impl<M, N, D, S, T, C, H> RuFiPlatform<M, N, D, S, T, C, H>
+where
+    M: Mailbox,
+    N: Network,
+    D: Discovery,
+    S: NbrSensorSetup,
+    T: Time,
+    C: Scheduler,
+    H: Fn(&Export),
+{
+    /// Creates a new platform
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mailbox: M,
+        network: N,
+        context: Context,
+        discovery: D,
+        setup: S,
+        time: T,
+        scheduler: C,
+        hooks: Vec<H>,
+    ) -> Self {
+        RuFiPlatform {
+            mailbox,
+            network,
+            context,
+            discovery,
+            discovered_nbrs: vec![],
+            nbr_sensor_setup: setup,
+            time,
+            scheduler,
+            hooks,
+            last_round: None,
+        }
+    }
+
+    /// Runs indefinitely the program on the platform
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The aggregate program to be executed
+    ///
+    /// # Generic Arguments
+    ///
+    /// * `P` - The type of the aggregate program, it must be a function that takes a [RoundVM] and returns a result of type `A`
+    /// * `A` - The type of the result of the aggregate program
+    pub async fn run_forever<P, A>(mut self, program: P) -> Result<(), Box<dyn Error>>
+    where
+        P: Fn(&mut RoundVM) -> A + Copy,
+        A: Clone + 'static + FromStr + Display,
+    {
+        loop {
+            self.step(program).await?;
+        }
+    }
+
+    /// Runs the program on the platform for `n` rounds
+    pub async fn run_n_cycles<P, A>(mut self, program: P, n: usize) -> Result<(), Box<dyn Error>>
+    where
+        P: Fn(&mut RoundVM) -> A + Copy,
+        A: Clone + 'static + FromStr + Display,
+    {
+        for _ in 0..n {
+            self.step(program).await?;
+        }
+        Ok(())
+    }
+
+    /// Executes a round, calls the hooks with its export and waits for the next one
+    async fn step<P, A>(&mut self, program: P) -> Result<(), Box<dyn Error>>
+    where
+        P: Fn(&mut RoundVM) -> A,
+        A: Clone + 'static + FromStr + Display,
+    {
+        let start = Instant::now();
+        let export = self.single_cycle(program).await?;
+
+        for hook in self.hooks.iter() {
+            hook(&export);
+        }
+        let delay = self.scheduler.next_round(&RoundReport {
+            duration: start.elapsed(),
+            export: &export,
+        });
+        self.wait(delay).await
+    }
+
+    /// Performs a single step of the execution cycle of an aggregate program
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The aggregate program to be executed
+    ///
+    /// # Generic Arguments
+    ///
+    /// * `P` - The type of the aggregate program, it must be a function that takes a [RoundVM] and returns a result of type `A`
+    /// * `A` - The type of the result of the aggregate program
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Export, Box<dyn Error>>` - The export of the device
+    async fn single_cycle<P, A>(&mut self, program: P) -> Result<Export, Box<dyn Error>>
+    where
+        P: Fn(&mut RoundVM) -> A,
+        A: Clone + 'static + FromStr + Display,
+    {
+        // STEP 1: Discover neighbours
+        let nbrs = self.discovery.discover_neighbors().await;
+        // STEP 2: Keep the subscriptions in sync with the discovered neighbours
+        // A change that fails is attempted again in the next cycle
+        let (joined, left) = subscription_changes(&self.discovered_nbrs, &nbrs);
+        for nbr in joined {
+            match self.network.subscribe(nbr).await {
+                Ok(()) => self.discovered_nbrs.push(nbr),
+                Err(e) => println!("Error subscribing to {}: {}", nbr, e),
+            }
+        }
+        for nbr in left {
+            match self.network.unsubscribe(nbr).await {
+                Ok(()) => self.discovered_nbrs.retain(|n| *n != nbr),
+                Err(e) => println!("Error unsubscribing from {}: {}", nbr, e),
+            }
+        }
+
+        // The mailbox always retains the exports of the device itself
+        let self_id = *self.context.self_id();
+        let mut known = nbrs;
+        if !known.contains(&self_id) {
+            known.push(self_id);
+        }
+        self.mailbox.update_neighbours(&known);
+
+        //STEP 3: Execute a round with the neighbouring exports stored in the mailbox
+        let self_export = execute_round(
+            &self.context,
+            &mut self.mailbox,
+            &self.nbr_sensor_setup,
+            &mut self.last_round,
+            program,
+        );
+
+        //STEP 4: Publish the export
+        if let Some(msg) = encode(self_id, self_export.clone()) {
+            if let Err(e) = self.network.send(self_id, msg).await {
+                println!("Error sending the message: {}", e);
+            }
+        }
+        Ok(self_export)
+    }
+
+    /// Receives the neighbouring exports from the network until `delay` has elapsed, or until
+    /// the scheduler wakes up because of an event.
+    ///
+    /// A pending reception is dropped when the time is up, so [Network::receive] must not lose
+    /// messages when it is cancelled.
+    async fn wait(&mut self, delay: Duration) -> Result<(), Box<dyn Error>> {
+        let self_id = *self.context.self_id();
+        let interval = self.scheduler.poll_interval();
+        let mut timeout = pin!(self.time.sleep(delay));
+        let mut poll = self.time.sleep(interval.unwrap_or(delay));
+        // Whether more messages can be received before the next round
+        let mut receiving = true;
+        loop {
+            let event = tokio::select! {
+                _ = &mut timeout => return Ok(()),
+                _ = &mut poll, if interval.is_some() => Event::Poll,
+                update = self.network.receive(), if receiving => match update {
+                    Ok(NetworkUpdate::None) => {
+                        receiving = false;
+                        continue;
+                    }
+                    Err(e) => {
+                        // The network may fail again right away, so it is left alone until the
+                        // next round
+                        println!("Error receiving from the network: {}", e);
+                        receiving = false;
+                        continue;
+                    }
+                    Ok(update) => match handle_update(&mut self.mailbox, update) {
+                        Ok(Some(source)) if source != self_id => Event::Message { source },
+                        Ok(_) => continue,
+                        Err(e) => {
+                            // A transient error of the network does not stop the device
+                            println!("Error receiving from the network: {}", e);
+                            continue;
+                        }
+                    },
+                }
+            };
+            if event == Event::Poll {
+                poll = self.time.sleep(interval.unwrap_or(delay));
+            }
+            if self.scheduler.wakes_on(&event) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::discovery::asynchronous::Discovery;
+    use crate::mailbox::Messages;
+    use crate::message::Message;
+    use crate::network::NetworkResult;
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use rf_core::context::NbrSensors;
+    use rf_core::lang::{foldhood, mid, nbr, rep};
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+    struct ChannelNetwork {
+        nbrs: Vec<UnboundedSender<Bytes>>,
+        receiver: UnboundedReceiver<Bytes>,
+    }
+
+    #[async_trait]
+    impl Network for ChannelNetwork {
+        async fn send(&mut self, _source: i32, msg: Bytes) -> NetworkResult<()> {
+            for nbr in self.nbrs.iter() {
+                // The neighbour may have already stopped
+                nbr.send(msg.clone()).unwrap_or(());
+            }
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+            Ok(match self.receiver.recv().await {
+                Some(msg) => NetworkUpdate::Update { msg },
+                None => NetworkUpdate::None,
+            })
+        }
+    }
+
+    struct LineDiscovery(i32);
+
+    #[async_trait]
+    impl Discovery for LineDiscovery {
+        async fn discover_neighbors(&self) -> Vec<i32> {
+            vec![self.0 - 1, self.0, self.0 + 1]
+        }
+    }
+
+    struct NoSetup;
+
+    impl NbrSensorSetup for NoSetup {
+        fn nbr_sensor_setup(&self, _nbrs: Vec<i32>) -> NbrSensors {
+            Default::default()
+        }
+    }
+
+    struct FastTime;
+
+    #[async_trait]
+    impl Time for FastTime {
+        async fn sleep(&self, duration: Duration) {
+            tokio::time::sleep(duration / 100).await;
+        }
+    }
+
+    struct EverySecond;
+
+    impl Scheduler for EverySecond {
+        fn next_round(&mut self, _round: &RoundReport) -> Duration {
+            Duration::from_secs(1)
+        }
+    }
+
+    /// Executes a round as soon as a neighbour sends its export, or every hour otherwise.
+    struct OnMessage;
+
+    impl Scheduler for OnMessage {
+        fn next_round(&mut self, _round: &RoundReport) -> Duration {
+            Duration::from_secs(3600)
+        }
+
+        fn poll_interval(&self) -> Option<Duration> {
+            Some(Duration::from_secs(3600))
+        }
+
+        fn wakes_on(&mut self, event: &Event) -> bool {
+            matches!(event, Event::Message { .. })
+        }
+    }
+
+    #[derive(Default)]
+    struct TestMailbox(Messages);
+
+    impl Mailbox for TestMailbox {
+        fn enqueue(&mut self, msg: Message) {
+            self.0.insert(msg.source, msg);
+        }
+
+        fn messages(&mut self) -> Messages {
+            self.0.clone()
+        }
+    }
+
+    fn max_id(vm: &mut RoundVM) -> i32 {
+        let id = mid(vm);
+        rep(
+            vm,
+            |_vm| id,
+            |vm1, max| foldhood(vm1, |_vm| max, |a, b| a.max(b), |vm2| nbr(vm2, |_vm| max)),
+        )
+    }
+
+    /// Runs [max_id] for 10 rounds on the line [1] -- [2] -- [3], with devices sharing the
+    /// runtime and scheduled by the given policy.
+    async fn run_max_id<C: Scheduler>(scheduler: fn() -> C) -> HashMap<i32, i32> {
+        let ids = [1, 2, 3];
+        let (senders, mut receivers): (HashMap<i32, _>, HashMap<i32, _>) = ids
+            .iter()
+            .map(|id| {
+                let (sender, receiver) = unbounded_channel();
+                ((*id, sender), (*id, receiver))
+            })
+            .unzip();
+        let results: Rc<RefCell<HashMap<i32, i32>>> = Default::default();
+        let platforms = ids.map(|id| {
+            let network = ChannelNetwork {
+                // Like a broker, the network delivers the messages to their sender as well
+                nbrs: [id - 1, id, id + 1]
+                    .iter()
+                    .filter_map(|nbr| senders.get(nbr).cloned())
+                    .collect(),
+                receiver: receivers.remove(&id).unwrap(),
+            };
+            let context = Context::new(
+                id,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            );
+            let results = results.clone();
+            let hook = move |export: &Export| {
+                results.borrow_mut().insert(id, export.root::<i32>());
+            };
+            RuFiPlatform::new(
+                TestMailbox::default(),
+                network,
+                context,
+                LineDiscovery(id),
+                NoSetup,
+                FastTime,
+                scheduler(),
+                vec![hook],
+            )
+            .run_n_cycles(max_id, 10)
+        });
+        drop(senders);
+
+        let [p1, p2, p3] = platforms;
+        let (r1, r2, r3) = tokio::join!(p1, p2, p3);
+        assert!(r1.is_ok() && r2.is_ok() && r3.is_ok());
+        results.take()
+    }
+
+    #[tokio::test]
+    async fn test_devices_share_runtime() {
+        let results = run_max_id(|| EverySecond).await;
+        assert_eq!(results, HashMap::from([(1, 3), (2, 3), (3, 3)]));
+    }
+
+    #[tokio::test]
+    async fn test_rounds_triggered_by_messages() {
+        // Without the messages of the neighbours, the rounds would be an hour apart
+        let results = tokio::time::timeout(Duration::from_secs(5), run_max_id(|| OnMessage)).await;
+        assert_eq!(results.unwrap(), HashMap::from([(1, 3), (2, 3), (3, 3)]));
+    }
+
+    /// A network that delivers the given updates, then nothing else.
+    struct FailingNetwork(Vec<Result<NetworkUpdate, String>>);
+
+    #[async_trait]
+    impl Network for FailingNetwork {
+        async fn send(&mut self, _source: i32, _msg: Bytes) -> NetworkResult<()> {
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+            self.0
+                .pop()
+                .unwrap_or(Ok(NetworkUpdate::None))
+                .map_err(|e| e.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_network_errors_do_not_stop_the_device() {
+        let network = FailingNetwork(vec![
+            Err("Connection reset".to_string()),
+            Ok(NetworkUpdate::Update {
+                msg: Bytes::from("not a message"),
+            }),
+            Ok(NetworkUpdate::Err {
+                reason: "Connection refused".to_string(),
+            }),
+        ]);
+        let context = Context::new(
+            1,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let platform = RuFiPlatform::new(
+            TestMailbox::default(),
+            network,
+            context,
+            LineDiscovery(1),
+            NoSetup,
+            FastTime,
+            EverySecond,
+            Vec::<fn(&Export)>::new(),
+        );
+        assert!(platform.run_n_cycles(max_id, 3).await.is_ok());
+    }
+
+    /// A network that only records the neighbours it is subscribed to.
+    #[derive(Default)]
+    struct SubscriptionNetwork(Arc<Mutex<HashSet<i32>>>);
+
+    #[async_trait]
+    impl Network for SubscriptionNetwork {
+        async fn send(&mut self, _source: i32, _msg: Bytes) -> NetworkResult<()> {
+            Ok(())
+        }
+
+        async fn receive(&mut self) -> NetworkResult<NetworkUpdate> {
+            Ok(NetworkUpdate::None)
+        }
+
+        async fn subscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+            self.0.lock().unwrap().insert(nbr);
+            Ok(())
+        }
+
+        async fn unsubscribe(&mut self, nbr: i32) -> NetworkResult<()> {
+            self.0.lock().unwrap().remove(&nbr);
+            Ok(())
+        }
+    }
+
+    struct ChangingDiscovery(Arc<Mutex<Vec<i32>>>);
+
+    #[async_trait]
+    impl Discovery for ChangingDiscovery {
+        async fn discover_neighbors(&self) -> Vec<i32> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_follow_discovery() {
+        let nbrs = Arc::new(Mutex::new(vec![1, 2]));
+        let network = SubscriptionNetwork::default();
+        let subscriptions = network.0.clone();
+        let context = Context::new(
+            1,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+        let mut platform = RuFiPlatform::new(
+            TestMailbox::default(),
+            network,
+            context,
+            ChangingDiscovery(nbrs.clone()),
+            NoSetup,
+            FastTime,
+            EverySecond,
+            Vec::<fn(&Export)>::new(),
+        );
+
+        platform.single_cycle(max_id).await.unwrap();
+        assert_eq!(*subscriptions.lock().unwrap(), HashSet::from([1, 2]));
+
+        *nbrs.lock().unwrap() = vec![1, 3];
+        platform.single_cycle(max_id).await.unwrap();
+        assert_eq!(*subscriptions.lock().unwrap(), HashSet::from([1, 3]));
+    }
+}
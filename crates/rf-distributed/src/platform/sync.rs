@@ -1,28 +1,34 @@
 use crate::discovery::nbr_sensors_setup::NbrSensorSetup;
 use crate::discovery::Discovery;
-use crate::mailbox::{AsStates, Mailbox};
-use crate::message::Message;
-use crate::network::{sync::Network, NetworkUpdate};
+use crate::mailbox::Mailbox;
+use crate::network::sync::Network;
+use crate::network::NetworkUpdate;
+use crate::platform::{encode, execute_round, handle_update, subscription_changes};
+use crate::scheduler::{Event, RoundReport, Scheduler};
 use crate::time::Time;
-use bytes::Bytes;
 use rf_core::context::Context;
 use rf_core::export::Export;
-use rf_core::lang::execution::round;
 use rf_core::vm::round_vm::RoundVM;
 use std::error::Error;
 use std::fmt::Display;
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-/// This struct represents the platform on which the program is executed
-pub struct RuFiPlatform<M, N, D, S, T, H>
-    where
-        M: Mailbox,
-        N: Network,
-        D: Discovery,
-        S: NbrSensorSetup,
-        T: Time,
-        H: Fn(&Export) -> (),
+/// The maximum number of messages received before a round. The others are received before the
+/// next rounds.
+const MAX_DRAINED_MESSAGES: usize = 1000;
+
+/// This struct represents the platform on which the program is executed, with the rounds
+/// scheduled by a [Scheduler]
+pub struct RuFiPlatform<M, N, D, S, T, C, H>
+where
+    M: Mailbox,
+    N: Network,
+    D: Discovery,
+    S: NbrSensorSetup,
+    T: Time,
+    C: Scheduler,
+    H: Fn(&Export),
 {
     mailbox: M,
     network: N,
@@ -31,20 +37,33 @@ pub struct RuFiPlatform<M, N, D, S, T, H>
     discovered_nbrs: Vec<i32>,
     nbr_sensor_setup: S,
     time: T,
+    scheduler: C,
     hooks: Vec<H>,
+    last_round: Option<SystemTime>,
 }
 
-impl<M, N, D, S, T, H> RuFiPlatform<M, N, D, S, T, H>
-    where
-        M: Mailbox,
-        N: Network,
-        D: Discovery,
-        S: NbrSensorSetup,
-        T: Time,
-        H: Fn(&Export) -> (),
+impl<M, N, D, S, T, C, H> RuFiPlatform<M, N, D, S, T, C, H>
+where
+    M: Mailbox,
+    N: Network,
+    D: Discovery,
+    S: NbrSensorSetup,
+    T: Time,
+    C: Scheduler,
+    H: Fn(&Export),
 {
     /// Creates a new platform
-    pub fn new(mailbox: M, network: N, context: Context, discovery: D, setup: S, time: T, hooks: Vec<H>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mailbox: M,
+        network: N,
+        context: Context,
+        discovery: D,
+        setup: S,
+        time: T,
+        scheduler: C,
+        hooks: Vec<H>,
+    ) -> Self {
         RuFiPlatform {
             mailbox,
             network,
@@ -53,7 +72,9 @@ impl<M, N, D, S, T, H> RuFiPlatform<M, N, D, S, T, H>
             discovered_nbrs: vec![],
             nbr_sensor_setup: setup,
             time,
+            scheduler,
             hooks,
+            last_round: None,
         }
     }
 
@@ -68,25 +89,77 @@ impl<M, N, D, S, T, H> RuFiPlatform<M, N, D, S, T, H>
     /// * `P` - The type of the aggregate program, it must be a function that takes a [RoundVM] and returns a [RoundVM] and a result of type `A`
     /// * `A` - The type of the result of the aggregate program
     pub fn run_forever<P, A>(mut self, program: P) -> Result<(), Box<dyn Error>>
-        where
-            P: Fn(&mut RoundVM) -> A + Copy,
-            A: Clone + 'static + FromStr + Display,
+    where
+        P: Fn(&mut RoundVM) -> A + Copy,
+        A: Clone + 'static + FromStr + Display,
     {
         loop {
+            let start = Instant::now();
             let export = self.single_cycle(program)?;
 
             for hook in self.hooks.iter() {
                 hook(&export);
             }
-            //sleep for one sec
-            self.time.sleep(Duration::from_secs(1));
+            let delay = self.scheduler.next_round(&RoundReport {
+                duration: start.elapsed(),
+                export: &export,
+            });
+            self.wait(delay)?;
+        }
+    }
+
+    /// Waits for the next round. If the scheduler observes the events, the neighbouring exports
+    /// are received in the meantime, and the wait ends as soon as the scheduler wakes up.
+    fn wait(&mut self, delay: Duration) -> Result<(), Box<dyn Error>> {
+        let Some(interval) = self.scheduler.poll_interval() else {
+            self.time.sleep(delay);
+            return Ok(());
+        };
+        let self_id = *self.context.self_id();
+        let deadline = Instant::now() + delay;
+        let mut next_poll = Instant::now() + interval;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            if now >= next_poll {
+                next_poll = now + interval;
+                if self.scheduler.wakes_on(&Event::Poll) {
+                    return Ok(());
+                }
+            }
+            let timeout = deadline.min(next_poll).saturating_duration_since(now);
+            let source = match self.network.receive_timeout(timeout) {
+                Ok(NetworkUpdate::None) => continue,
+                Err(e) => {
+                    // The network may fail again right away, so it is left alone until the next
+                    // round
+                    println!("Error receiving from the network: {}", e);
+                    self.time
+                        .sleep(deadline.saturating_duration_since(Instant::now()));
+                    return Ok(());
+                }
+                Ok(update) => match handle_update(&mut self.mailbox, update) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        println!("Error receiving from the network: {}", e);
+                        continue;
+                    }
+                },
+            };
+            if let Some(source) = source.filter(|source| *source != self_id) {
+                if self.scheduler.wakes_on(&Event::Message { source }) {
+                    return Ok(());
+                }
+            }
         }
     }
 
     pub fn run_n_cycles<P, A>(mut self, program: P, n: usize) -> Result<(), Box<dyn Error>>
-        where
-            P: Fn(&mut RoundVM) -> A + Copy,
-            A: Clone + 'static + FromStr + Display,
+    where
+        P: Fn(&mut RoundVM) -> A + Copy,
+        A: Clone + 'static + FromStr + Display,
     {
         for _ in 0..n {
             self.single_cycle(program)?;
@@ -110,79 +183,76 @@ impl<M, N, D, S, T, H> RuFiPlatform<M, N, D, S, T, H>
     ///
     /// # Returns
     ///
-    /// * `Result<(), Box<dyn Error>>` - The result of the execution
-    fn single_cycle<P, A>(
-        &mut self,
-        program: P,
-    ) -> Result<Export, Box<dyn Error>>
-        where
-            P: Fn(&mut RoundVM) -> A,
-            A: Clone + 'static + FromStr + Display,
-            M: Mailbox,
-            N: Network,
-            S: NbrSensorSetup,
+    /// * `Result<Export, Box<dyn Error>>` - The export of the device
+    pub fn single_cycle<P, A>(&mut self, program: P) -> Result<Export, Box<dyn Error>>
+    where
+        P: Fn(&mut RoundVM) -> A,
+        A: Clone + 'static + FromStr + Display,
+        M: Mailbox,
+        N: Network,
+        S: NbrSensorSetup,
     {
         // STEP 1: Discover neighbours
         let nbrs = self.discovery.discover_neighbors();
-        // STEP 2: Subscribe to the topics of the neighbours
-        let subscriptions: Vec<i32> = nbrs
-            .clone()
-            .into_iter()
-            .filter(|n| !self.discovered_nbrs.contains(n))
-            .collect();
-        self.discovered_nbrs.extend(subscriptions);
+        // STEP 2: Keep the subscriptions in sync with the discovered neighbours
+        // A change that fails is attempted again in the next cycle
+        let (joined, left) = subscription_changes(&self.discovered_nbrs, &nbrs);
+        for nbr in joined {
+            match self.network.subscribe(nbr) {
+                Ok(()) => self.discovered_nbrs.push(nbr),
+                Err(e) => println!("Error subscribing to {}: {}", nbr, e),
+            }
+        }
+        for nbr in left {
+            match self.network.unsubscribe(nbr) {
+                Ok(()) => self.discovered_nbrs.retain(|n| *n != nbr),
+                Err(e) => println!("Error unsubscribing from {}: {}", nbr, e),
+            }
+        }
 
-        //STEP 3: Retrieve the neighbouring exports from the mailbox
-        let states = self.mailbox.messages().as_states();
+        // The mailbox always retains the exports of the device itself
+        let self_id = *self.context.self_id();
+        let mut known = nbrs;
+        if !known.contains(&self_id) {
+            known.push(self_id);
+        }
+        self.mailbox.update_neighbours(&known);
 
-        //STEP 4: Execute a round
-        let nbr_sensors = self.nbr_sensor_setup.nbr_sensor_setup(states.keys().cloned().collect());
-        let context = Context::new(
-            *self.context.self_id(),
-            self.context.local_sensors().clone(),
-            nbr_sensors,
-            states,
+        //STEP 3: Receive the neighbouring exports that have arrived since the last round
+        // The messages beyond MAX_DRAINED_MESSAGES are left for the next round, so that a
+        // neighbour that sends faster than they are received does not stop the device
+        for _ in 0..MAX_DRAINED_MESSAGES {
+            match self.network.try_receive() {
+                Ok(NetworkUpdate::None) => break,
+                Ok(update) => {
+                    if let Err(e) = handle_update(&mut self.mailbox, update) {
+                        println!("Error receiving from the network: {}", e);
+                    }
+                }
+                Err(e) => {
+                    // The network may fail again right away, so it is left alone until the next
+                    // round
+                    println!("Error receiving from the network: {}", e);
+                    break;
+                }
+            }
+        }
+
+        //STEP 4: Execute a round with the neighbouring exports stored in the mailbox
+        let self_export = execute_round(
+            &self.context,
+            &mut self.mailbox,
+            &self.nbr_sensor_setup,
+            &mut self.last_round,
+            program,
         );
-        //println!("CONTEXT: {:?}", context);
-        let mut vm = RoundVM::new(context);
-        vm.new_export_stack();
-        let result = round(&mut vm, program);
-        let self_export: Export = vm.export_data().clone();
-        println!("OUTPUT: {}\nEXPORT: {}\n", result, self_export);
 
         //STEP 5: Publish the export
-        let msg = Message::new(*vm.self_id(), self_export.clone(), std::time::SystemTime::now());
-        if let Ok(msg_ser) = serde_json::to_vec(&msg) {
-            if let Err(e) = self.network.send(*vm.self_id(), Bytes::from(msg_ser)) {
+        if let Some(msg) = encode(self_id, self_export.clone()) {
+            if let Err(e) = self.network.send(self_id, msg) {
                 println!("Error sending the message: {}", e);
             }
-        } else {
-            println!("Error while serializing the message");
-        }
-
-        //STEP 6: Receive the neighbouring exports from the network
-        match self.network.receive() {
-            Ok(NetworkUpdate::Update { msg }) => {
-                if let Ok(msg) = serde_json::from_slice(&msg) {
-                    self.mailbox.enqueue(msg);
-                    Ok(self_export)
-                } else {
-                    Err("Error deserializing the message".into())
-                }
-            }
-            Ok(NetworkUpdate::None) => {
-                println!("No message received from the network");
-                Ok(self_export)
-            }
-            Ok(NetworkUpdate::Err { reason }) => {
-                println!("Error receiving from the network: {}", reason);
-                Err(reason.into())
-            }
-            _ => {
-                println!("Error receiving from the network");
-                Err("Error receiving from the network".into())
-            }
         }
+        Ok(self_export)
     }
 }
-
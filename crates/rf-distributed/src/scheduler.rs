@@ -0,0 +1,41 @@
+use rf_core::export::Export;
+use std::time::Duration;
+
+/// This struct describes the round that the platform has just executed.
+pub struct RoundReport<'a> {
+    /// How long the round took, including the communication with the neighbours
+    pub duration: Duration,
+    /// The export computed in the round
+    pub export: &'a Export,
+}
+
+/// This enum represents the events that may occur while the platform waits for the next round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A message of a neighbour has been received
+    Message { source: i32 },
+    /// The interval returned by [Scheduler::poll_interval] has elapsed
+    Poll,
+}
+
+/// This trait represents the policy deciding when the rounds of a device are executed
+pub trait Scheduler {
+    /// Computes how long to wait before the next round
+    ///
+    /// # Arguments
+    ///
+    /// * `round` - The round that has just been executed
+    fn next_round(&mut self, round: &RoundReport) -> Duration;
+
+    /// How often [Event::Poll] occurs while waiting. By default, the platform does not observe
+    /// any event and waits for the whole delay.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Decides whether the next round starts before the delay has elapsed, because of an event.
+    /// By default, it never does.
+    fn wakes_on(&mut self, _event: &Event) -> bool {
+        false
+    }
+}
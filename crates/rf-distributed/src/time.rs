@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+pub mod asynchronous;
+
 /// This trait deals with time operations
 pub trait Time {
     /// Sleep for the given duration
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// This trait deals with time operations without blocking the thread of the caller
+#[async_trait]
+pub trait Time {
+    /// Sleep for the given duration, yielding to the other tasks in the meantime
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The duration to sleep
+    async fn sleep(&self, duration: Duration);
+}
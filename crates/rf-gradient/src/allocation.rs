@@ -0,0 +1,104 @@
+use crate::blocks::{broadcast, Tagged};
+use crate::collect::collect;
+use crate::generalized_gradient;
+use rf_core::lang::builtins::spawn;
+use rf_core::lang::mid;
+use rf_core::vm::round_vm::RoundVM;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The state of a task as perceived by a device taking part in its allocation.
+///
+/// * `winner` - The id of the device the task is assigned to, or `i32::MAX` if it is not assigned yet.
+/// * `distance` - The distance of the device from the one that generated the task.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Allocation {
+    pub winner: i32,
+    pub distance: f64,
+}
+
+impl Allocation {
+    /// Whether the task is assigned to the given device.
+    pub fn is_assigned_to(&self, device: i32) -> bool {
+        self.winner == device
+    }
+}
+
+impl Display for Allocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.winner, self.distance)
+    }
+}
+
+impl FromStr for Allocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (winner, distance) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Allocation {
+            winner: winner.parse::<i32>().map_err(|e| e.to_string())?,
+            distance: distance.parse::<f64>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// Assigns each task to the capable device with the lowest cost, i.e. its distance from the
+/// device that generated the task divided by its capacity. Every task runs in a process spawned by
+/// the device generating it and spreading a gradient within `range`: the bids of the devices are
+/// collected along the gradient, and the device that generated the task broadcasts the winner
+/// back. Since bids are collected continuously, a task is reassigned when its winner fails.
+///
+/// A process ends once the device that generated the task stops generating it, e.g. because it
+/// has been completed.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `tasks` - The ids of the tasks generated by the current device, which must be unique in the network.
+/// * `capacity` - The capacity of the current device: the higher, the farther the tasks it wins.
+///   A device with no capacity never wins a task.
+/// * `range` - The maximum distance from the device generating a task at which bids are accepted.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The [Allocation] of each task the current device is taking part in, by task id.
+pub fn allocate_tasks<M>(
+    vm: &mut RoundVM,
+    tasks: &[i32],
+    capacity: f64,
+    range: f64,
+    metric: M,
+) -> HashMap<i32, Allocation>
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let self_id = mid(vm);
+    spawn(
+        vm,
+        |vm1, task| {
+            let origin = tasks.contains(&task);
+            let distance = generalized_gradient(vm1, origin, metric);
+            let cost = if capacity > 0.0 {
+                distance / capacity
+            } else {
+                f64::INFINITY
+            };
+            let best = collect(
+                vm1,
+                distance,
+                |a: Tagged<i32>, b| {
+                    if (b.distance, b.value) < (a.distance, a.value) {
+                        b
+                    } else {
+                        a
+                    }
+                },
+                Tagged::new(cost, if cost.is_finite() { self_id } else { i32::MAX }),
+                Tagged::new(f64::INFINITY, i32::MAX),
+            );
+            let winner = broadcast(vm1, origin, best.value, metric);
+            (Allocation { winner, distance }, distance <= range)
+        },
+        tasks,
+    )
+}
@@ -0,0 +1,129 @@
+use crate::generalized_gradient;
+use rf_core::lang::builtins::{foldhood_plus, mux};
+use rf_core::lang::{nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A value paired with the distance, or potential, used to rank it against the ones of the neighbours.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Tagged<V> {
+    pub(crate) distance: f64,
+    pub(crate) value: V,
+}
+
+impl<V> Tagged<V> {
+    pub(crate) fn new(distance: f64, value: V) -> Self {
+        Self { distance, value }
+    }
+}
+
+impl<V: Display> Display for Tagged<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.distance, self.value)
+    }
+}
+
+impl<V: FromStr> FromStr for Tagged<V> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (distance, value) = s.split_once(';').ok_or("Missing separator")?;
+        let distance = distance.parse::<f64>().map_err(|e| e.to_string())?;
+        let value = value.parse::<V>().map_err(|_| "Cannot parse value")?;
+        Ok(Tagged::new(distance, value))
+    }
+}
+
+/// Propagates a value outwards from a source along the gradient, accumulating it hop by hop.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `source` - Whether the current device is a source.
+/// * `field` - The value produced by the current device if it is a source.
+/// * `acc` - The function applied to the value each time it crosses a hop.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The accumulated value of the closest source, or `field` if no source is reachable.
+pub fn gradient_cast<V, A, M>(vm: &mut RoundVM, source: bool, field: V, acc: A, metric: M) -> V
+where
+    V: Clone + 'static + FromStr + Display,
+    A: Fn(V) -> V + Copy,
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    rep(
+        vm,
+        |_| Tagged::new(f64::INFINITY, field.clone()),
+        |vm1, prev| {
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| Tagged::new(0.0, field.clone()),
+                |vm2| {
+                    foldhood_plus(
+                        vm2,
+                        |_vm| Tagged::new(f64::INFINITY, field.clone()),
+                        |a, b| if b.distance < a.distance { b } else { a },
+                        |vm3| {
+                            let distance = nbr(vm3, |_vm| prev.distance) + metric(vm3);
+                            let value = nbr(vm3, |_vm| acc(prev.value.clone()));
+                            Tagged::new(distance, value)
+                        },
+                    )
+                },
+            )
+        },
+    )
+    .value
+}
+
+/// Broadcasts the value held by the closest source to every device.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `source` - Whether the current device is a source.
+/// * `value` - The value to broadcast if the current device is a source.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The value of the closest source, or the local `value` if no source is reachable.
+pub fn broadcast<V, M>(vm: &mut RoundVM, source: bool, value: V, metric: M) -> V
+where
+    V: Clone + 'static + FromStr + Display,
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    gradient_cast(vm, source, value, |v| v, metric)
+}
+
+/// Computes the distance between the closest source and the closest target, making it available
+/// to every device.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `source` - Whether the current device is a source.
+/// * `target` - Whether the current device is a target.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance between the two regions, or `f64::INFINITY` if they are not connected.
+pub fn distance_between<M>(vm: &mut RoundVM, source: bool, target: bool, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let to_target = generalized_gradient(vm, target, metric);
+    broadcast(vm, source, to_target, metric)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tagged_from_str() {
+        let tagged = Tagged::new(1.5, 7);
+        let parsed = tagged.to_string().parse::<Tagged<i32>>().unwrap();
+        assert_eq!(parsed, tagged);
+        assert!("1.5".parse::<Tagged<i32>>().is_err());
+    }
+}
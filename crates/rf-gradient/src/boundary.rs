@@ -0,0 +1,95 @@
+use crate::generalized_gradient;
+use rf_core::lang::builtins::foldhood_plus;
+use rf_core::lang::{branch, foldhood, nbr};
+use rf_core::vm::round_vm::RoundVM;
+
+/// Whether the current device lies on the edge of the area where `inside` holds, i.e. it is inside
+/// the area and at least one of its neighbours is not.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `inside` - Whether the current device satisfies the predicate defining the area.
+///
+/// # Returns
+/// `true` if the current device is on the edge of the area, `false` otherwise.
+pub fn is_edge(vm: &mut RoundVM, inside: bool) -> bool {
+    let outside_nbr = foldhood(
+        vm,
+        |_vm| false,
+        |a, b| a || b,
+        |vm1| !nbr(vm1, |_vm| inside),
+    );
+    inside && outside_nbr
+}
+
+/// Whether the current device lies on the physical border of the deployment. Since devices do not
+/// know their position, the border is detected through the density of the neighbourhood: a device
+/// is on the border when it has fewer than `expected_neighbours` neighbours within `radius`, as
+/// happens where part of its neighbourhood falls outside of the deployment.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `radius` - The radius of the neighbourhood considered.
+/// * `expected_neighbours` - The number of neighbours within `radius` of a device inside the deployment.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// `true` if the current device is on the border of the deployment, `false` otherwise.
+pub fn is_border<M>(vm: &mut RoundVM, radius: f64, expected_neighbours: i32, metric: M) -> bool
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let neighbours = foldhood_plus(
+        vm,
+        |_vm| 0,
+        |a, b| a + b,
+        |vm1| if metric(vm1) <= radius { 1 } else { 0 },
+    );
+    neighbours < expected_neighbours
+}
+
+/// Computes the distance from the edge of the area where `inside` holds, measured within the area.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `inside` - Whether the current device satisfies the predicate defining the area.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest device on the edge, or `f64::INFINITY` if the current device is
+/// outside of the area.
+pub fn distance_to_edge<M>(vm: &mut RoundVM, inside: bool, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let edge = is_edge(vm, inside);
+    branch(
+        vm,
+        || inside,
+        |vm1| generalized_gradient(vm1, edge, metric),
+        |_vm| f64::INFINITY,
+    )
+}
+
+/// Computes the distance from the physical border of the deployment, as detected by [is_border].
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `radius` - The radius of the neighbourhood considered.
+/// * `expected_neighbours` - The number of neighbours within `radius` of a device inside the deployment.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest device on the border.
+pub fn distance_to_border<M>(
+    vm: &mut RoundVM,
+    radius: f64,
+    expected_neighbours: i32,
+    metric: M,
+) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let border = is_border(vm, radius, expected_neighbours, metric);
+    generalized_gradient(vm, border, metric)
+}
@@ -0,0 +1,28 @@
+use crate::blocks::broadcast;
+use crate::generalized_gradient;
+use rf_core::vm::round_vm::RoundVM;
+
+/// Computes a self-healing channel between a source and a destination region.
+///
+/// A device belongs to the channel when it lies within `width` of a shortest path connecting the
+/// two regions. Since every distance is recomputed each round, the channel reroutes around devices
+/// that fail.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `source` - Whether the current device belongs to the source region.
+/// * `destination` - Whether the current device belongs to the destination region.
+/// * `width` - The tolerance allowed with respect to the shortest path.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// `true` if the current device is part of the channel, `false` otherwise.
+pub fn channel<M>(vm: &mut RoundVM, source: bool, destination: bool, width: f64, metric: M) -> bool
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let to_source = generalized_gradient(vm, source, metric);
+    let to_destination = generalized_gradient(vm, destination, metric);
+    let between = broadcast(vm, source, to_destination, metric);
+    between.is_finite() && to_source + to_destination <= between + width
+}
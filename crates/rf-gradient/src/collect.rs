@@ -0,0 +1,139 @@
+use crate::blocks::Tagged;
+use rf_core::lang::{foldhood, mid, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Identifies the neighbour with the lowest potential, which is the parent of the current device
+/// in the collection tree.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `potential` - The potential of the current device.
+///
+/// # Returns
+/// The id of the parent, or `i32::MAX` if no neighbour has a lower potential than the current device.
+pub fn find_parent(vm: &mut RoundVM, potential: f64) -> i32 {
+    let best = foldhood(
+        vm,
+        |_vm| Tagged::new(f64::INFINITY, i32::MAX),
+        |a, b| {
+            if (b.distance, b.value) < (a.distance, a.value) {
+                b
+            } else {
+                a
+            }
+        },
+        |vm1| Tagged::new(nbr(vm1, |_vm| potential), nbr(vm1, mid)),
+    );
+    if best.distance < potential {
+        best.value
+    } else {
+        i32::MAX
+    }
+}
+
+/// Collects values towards the devices with the lowest potential, typically the sources of a
+/// gradient, by descending the potential field. Each device sends its partial accumulation to a
+/// single parent, the neighbour with the lowest potential.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `potential` - The potential of the current device.
+/// * `acc` - The function used to accumulate values.
+/// * `local` - The value of the current device.
+/// * `null` - The neutral element of `acc`.
+///
+/// # Returns
+/// The accumulation of the values of the devices whose path towards the sink crosses the current
+/// device, itself included.
+pub fn collect<V, A>(vm: &mut RoundVM, potential: f64, acc: A, local: V, null: V) -> V
+where
+    V: Clone + 'static + FromStr + Display,
+    A: Fn(V, V) -> V + Copy,
+{
+    rep(
+        vm,
+        |_vm| local.clone(),
+        |vm1, v| {
+            let parent = find_parent(vm1, potential);
+            let self_id = mid(vm1);
+            let children = foldhood(
+                vm1,
+                |_vm| null.clone(),
+                acc,
+                |vm2| {
+                    let nbr_parent = nbr(vm2, |_vm| parent);
+                    let nbr_value = nbr(vm2, |_vm| v.clone());
+                    if nbr_parent == self_id {
+                        nbr_value
+                    } else {
+                        null.clone()
+                    }
+                },
+            );
+            acc(local.clone(), children)
+        },
+    )
+}
+
+/// Collects values towards the devices with the lowest potential along multiple paths. Each device
+/// splits its partial accumulation among all the neighbours with a lower potential, proportionally
+/// to the potential difference, which makes the collection more resilient to changes in the
+/// network than [collect].
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `potential` - The potential of the current device.
+/// * `acc` - The function used to accumulate values.
+/// * `local` - The value of the current device.
+/// * `null` - The neutral element of `acc`.
+/// * `split` - The function extracting the given fraction, in the range (0, 1], of a value.
+///
+/// # Returns
+/// The accumulation of the values flowing through the current device, itself included.
+pub fn collect_multi_path<V, A, S>(
+    vm: &mut RoundVM,
+    potential: f64,
+    acc: A,
+    local: V,
+    null: V,
+    split: S,
+) -> V
+where
+    V: Clone + 'static + FromStr + Display,
+    A: Fn(V, V) -> V + Copy,
+    S: Fn(V, f64) -> V + Copy,
+{
+    rep(
+        vm,
+        |_vm| local.clone(),
+        |vm1, v| {
+            let total_weight = foldhood(
+                vm1,
+                |_vm| 0.0,
+                |a, b| a + b,
+                |vm2| (potential - nbr(vm2, |_vm| potential)).max(0.0),
+            );
+            let children = foldhood(
+                vm1,
+                |_vm| null.clone(),
+                acc,
+                |vm2| {
+                    let nbr_potential = nbr(vm2, |_vm| potential);
+                    let nbr_total_weight = nbr(vm2, |_vm| total_weight);
+                    let nbr_value = nbr(vm2, |_vm| v.clone());
+                    if nbr_potential.is_finite()
+                        && nbr_potential > potential
+                        && nbr_total_weight > 0.0
+                    {
+                        split(nbr_value, (nbr_potential - potential) / nbr_total_weight)
+                    } else {
+                        null.clone()
+                    }
+                },
+            );
+            acc(local.clone(), children)
+        },
+    )
+}
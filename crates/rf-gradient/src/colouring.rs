@@ -0,0 +1,229 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rf_core::lang::builtins::{foldhood_plus, Ids};
+use rf_core::lang::{mid, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The colour chosen by a device running [colouring]: the choice is tentative until the device
+/// finds that no neighbour has chosen the same colour, then it is fixed. A negative colour means
+/// that the device has not chosen yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Choice {
+    colour: i32,
+    fixed: bool,
+}
+
+impl Display for Choice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.colour, self.fixed)
+    }
+}
+
+impl FromStr for Choice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (colour, fixed) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Choice {
+            colour: colour.parse::<i32>().map_err(|e| e.to_string())?,
+            fixed: fixed.parse::<bool>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// Whether a device running [maximal_independent_set] belongs to the set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Undecided,
+    Selected,
+    Excluded,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            Status::Undecided => "undecided",
+            Status::Selected => "selected",
+            Status::Excluded => "excluded",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "undecided" => Ok(Status::Undecided),
+            "selected" => Ok(Status::Selected),
+            "excluded" => Ok(Status::Excluded),
+            _ => Err(format!("Unknown status {}", s)),
+        }
+    }
+}
+
+/// The state of a device running [maximal_independent_set], together with the random number it
+/// drew to compete with its undecided neighbours. A negative number means that the device has not
+/// drawn yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    status: Status,
+    draw: f64,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.status, self.draw)
+    }
+}
+
+impl FromStr for Candidate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (status, draw) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Candidate {
+            status: status.parse::<Status>()?,
+            draw: draw.parse::<f64>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// Draws a random number in the range [0, 1) that changes at every round. The random number
+/// generator of the VM is recreated at each round, so the state of the sequence is kept with
+/// [rep], starting from a number drawn from the generator of the VM: seeding the devices makes
+/// the sequence reproducible.
+fn round_random(vm: &mut RoundVM) -> f64 {
+    let state = rep(
+        vm,
+        |vm1| vm1.rng().gen::<u64>(),
+        |_vm, state| StdRng::seed_from_u64(state).gen::<u64>(),
+    );
+    StdRng::seed_from_u64(!state).gen::<f64>()
+}
+
+/// Computes a maximal independent set of the network: no two neighbours are both selected, and
+/// every device that is not selected has a selected neighbour. At every round, each undecided
+/// device draws a random number and shares it with its neighbours: it joins the set if the number
+/// it shared in the previous round is the lowest among its undecided neighbours, and it leaves
+/// the competition when a neighbour joins the set. The set repairs itself when the neighbourhood
+/// changes: excluded devices without selected neighbours compete again, and of two neighbours
+/// selected at the same time the one with the lowest id stays in the set.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+///
+/// # Returns
+/// `true` if the current device belongs to the set, `false` otherwise.
+pub fn maximal_independent_set(vm: &mut RoundVM) -> bool {
+    let draw = round_random(vm);
+    let candidate = rep(
+        vm,
+        |_vm| Candidate {
+            status: Status::Undecided,
+            draw: -1.0,
+        },
+        |vm1, candidate| {
+            let excluded = foldhood_plus(
+                vm1,
+                |_vm| false,
+                |a, b| a || b,
+                |vm2| {
+                    let nbr_candidate = nbr(vm2, |_vm| candidate);
+                    let nbr_id = nbr(vm2, mid);
+                    nbr_candidate.status == Status::Selected
+                        && (candidate.status != Status::Selected || nbr_id < mid(vm2))
+                },
+            );
+            let beaten = foldhood_plus(
+                vm1,
+                |_vm| false,
+                |a, b| a || b,
+                |vm2| {
+                    let nbr_candidate = nbr(vm2, |_vm| candidate);
+                    let nbr_id = nbr(vm2, mid);
+                    nbr_candidate.status == Status::Undecided
+                        && (nbr_candidate.draw, nbr_id) < (candidate.draw, mid(vm2))
+                },
+            );
+            let status = match candidate.status {
+                _ if excluded => Status::Excluded,
+                Status::Undecided if candidate.draw >= 0.0 && !beaten => Status::Selected,
+                Status::Selected => Status::Selected,
+                _ => Status::Undecided,
+            };
+            Candidate { status, draw }
+        },
+    );
+    candidate.status == Status::Selected
+}
+
+/// Computes a proper colouring of the network, in which neighbours always have different colours.
+/// At every round, each device that has not fixed its colour yet draws a tentative colour among
+/// the ones not fixed by its neighbours, and shares it with them: the colour is fixed in the next
+/// round if no neighbour has chosen it in the meantime. Colours are drawn from the first degree
+/// plus one, so that at most one colour more than the maximum degree of the network is used. Of
+/// two neighbours that fix the same colour at the same time, the one with the lowest id keeps it.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+///
+/// # Returns
+/// The colour of the current device, starting from 0.
+pub fn colouring(vm: &mut RoundVM) -> i32 {
+    let draw = round_random(vm);
+    let degree = foldhood_plus(vm, |_vm| 0, |a, b| a + b, |_vm| 1);
+    let choice = rep(
+        vm,
+        |_vm| Choice {
+            colour: -1,
+            fixed: false,
+        },
+        |vm1, choice| {
+            let taken = foldhood_plus(
+                vm1,
+                |_vm| Ids(vec![]),
+                |mut a, b| {
+                    a.0.extend(b.0);
+                    a
+                },
+                |vm2| {
+                    let nbr_choice = nbr(vm2, |_vm| choice);
+                    if nbr_choice.fixed {
+                        Ids(vec![nbr_choice.colour])
+                    } else {
+                        Ids(vec![])
+                    }
+                },
+            );
+            let clash = foldhood_plus(
+                vm1,
+                |_vm| false,
+                |a, b| a || b,
+                |vm2| {
+                    let nbr_choice = nbr(vm2, |_vm| choice);
+                    let nbr_id = nbr(vm2, mid);
+                    // A fixed colour is only given up to a neighbour that fixed it too
+                    nbr_choice.colour == choice.colour
+                        && (!choice.fixed || (nbr_choice.fixed && nbr_id < mid(vm2)))
+                },
+            );
+            if choice.colour >= 0 && !clash {
+                Choice {
+                    colour: choice.colour,
+                    fixed: true,
+                }
+            } else {
+                let free: Vec<i32> = (0..=degree).filter(|c| !taken.0.contains(c)).collect();
+                Choice {
+                    colour: free[(draw * free.len() as f64) as usize],
+                    fixed: false,
+                }
+            }
+        },
+    );
+    choice.colour
+}
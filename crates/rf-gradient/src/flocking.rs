@@ -0,0 +1,66 @@
+use rf_core::geometry::Vector2D;
+use rf_core::lang::builtins::{mean_hood_plus, sum_hood_plus};
+use rf_core::lang::nbr;
+use rf_core::vm::round_vm::RoundVM;
+
+/// The parameters of the flocking rules.
+///
+/// * `separation_distance` - The distance below which neighbours are avoided.
+/// * `separation` - The weight of the separation rule.
+/// * `alignment` - The weight of the alignment rule.
+/// * `cohesion` - The weight of the cohesion rule.
+/// * `max_force` - The maximum length of the steering vector.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flocking {
+    pub separation_distance: f64,
+    pub separation: f64,
+    pub alignment: f64,
+    pub cohesion: f64,
+    pub max_force: f64,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Flocking {
+            separation_distance: 1.0,
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0,
+            max_force: 1.0,
+        }
+    }
+}
+
+/// Computes the steering vector of the current device according to the Reynolds flocking rules:
+/// * separation: steer away from the neighbours closer than the separation distance, the more the
+///   closer they are;
+/// * alignment: steer towards the average velocity of the neighbours;
+/// * cohesion: steer towards the average position of the neighbours.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `velocity` - The velocity of the current device.
+/// * `params` - The parameters of the flocking rules.
+/// * `nbr_vector` - The function estimating the position of the neighbour currently being folded
+///   relative to the current device, such as [rf_core::lang::builtins::nbr_vector].
+///
+/// # Returns
+/// The steering vector, whose length is at most `params.max_force`.
+pub fn flock<P>(vm: &mut RoundVM, velocity: Vector2D, params: Flocking, nbr_vector: P) -> Vector2D
+where
+    P: Fn(&mut RoundVM) -> Vector2D + Copy,
+{
+    let separation = sum_hood_plus(vm, |vm1| {
+        let offset = nbr_vector(vm1);
+        let distance = offset.norm();
+        if distance > 0.0 && distance < params.separation_distance {
+            -offset / (distance * distance)
+        } else {
+            Vector2D::zero()
+        }
+    });
+    let alignment = mean_hood_plus(vm, |vm1| nbr(vm1, |_vm| velocity) - velocity);
+    let cohesion = mean_hood_plus(vm, nbr_vector);
+    (separation * params.separation + alignment * params.alignment + cohesion * params.cohesion)
+        .limit(params.max_force)
+}
@@ -0,0 +1,238 @@
+use rf_core::lang::{foldhood, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The mass held by a device running [push_sum]: the ratio between `sum` and `weight` is the
+/// estimate of the average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mass {
+    sum: f64,
+    weight: f64,
+}
+
+impl Display for Mass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.sum, self.weight)
+    }
+}
+
+impl FromStr for Mass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (sum, weight) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Mass {
+            sum: sum.parse::<f64>().map_err(|e| e.to_string())?,
+            weight: weight.parse::<f64>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// A value spread by gossip, which is forgotten after `ttl` rounds without being refreshed by the
+/// device that originated it.
+#[derive(Debug, Clone, PartialEq)]
+struct Rumour<V> {
+    ttl: i32,
+    value: V,
+}
+
+impl<V: Display> Display for Rumour<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.ttl, self.value)
+    }
+}
+
+impl<V: FromStr> FromStr for Rumour<V> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (ttl, value) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Rumour {
+            ttl: ttl.parse::<i32>().map_err(|e| e.to_string())?,
+            value: value.parse::<V>().map_err(|_| "Cannot parse value")?,
+        })
+    }
+}
+
+/// Counts the neighbours of the current device, itself excluded.
+fn degree(vm: &mut RoundVM) -> f64 {
+    foldhood(vm, |_vm| -1.0, |a, b| a + b, |_vm| 1.0)
+}
+
+/// Whether the current device has run at least `rounds` rounds.
+fn has_run(vm: &mut RoundVM, rounds: i32) -> bool {
+    rep(vm, |_vm| 0, |_vm, n| (n + 1).min(rounds)) >= rounds
+}
+
+/// Reaches a consensus on the average of the values of the devices by repeatedly moving the
+/// estimate of each device towards the ones of its neighbours, with Metropolis-Hastings weights.
+/// Since devices do not update at the same time, the consensus only approximates the average: use
+/// [push_sum] for exact averages.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `value` - The value of the current device, used as initial estimate.
+///
+/// # Returns
+/// The estimate of the average of the values of the network.
+pub fn neighbour_average(vm: &mut RoundVM, value: f64) -> f64 {
+    let self_degree = degree(vm);
+    rep(
+        vm,
+        |_vm| value,
+        |vm1, estimate| {
+            estimate
+                + foldhood(
+                    vm1,
+                    |_vm| 0.0,
+                    |a, b| a + b,
+                    |vm2| {
+                        let nbr_estimate = nbr(vm2, |_vm| estimate);
+                        let nbr_degree = nbr(vm2, |_vm| self_degree);
+                        (nbr_estimate - estimate) / (1.0 + self_degree.max(nbr_degree))
+                    },
+                )
+        },
+    )
+}
+
+/// Computes the average of the values of the devices with the push-sum protocol: every device
+/// splits its mass evenly among itself and its neighbours, so that the total mass is preserved and
+/// the ratio between sum and weight converges to the exact average, as long as the neighbourhood
+/// does not change.
+///
+/// A device reads the latest share of each neighbour at every round, so the mass is only preserved
+/// if every neighbour runs exactly once between two rounds of the device: a share is lost when the
+/// neighbour runs twice in the meantime, and counted twice when it does not run at all. Under other
+/// schedulings the estimate drifts away from the average.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `value` - The value of the current device.
+///
+/// # Returns
+/// The estimate of the average of the values of the network.
+pub fn push_sum(vm: &mut RoundVM, value: f64) -> f64 {
+    // Mass is exchanged from the second round on, when all the neighbours are known: otherwise
+    // the ones that have not run yet would be missed when splitting it
+    let active = has_run(vm, 2);
+    let shares = degree(vm) + 1.0;
+    let mass = rep(
+        vm,
+        |_vm| Mass {
+            sum: value,
+            weight: 1.0,
+        },
+        |vm1, mass| {
+            let share = Mass {
+                sum: mass.sum / shares,
+                weight: mass.weight / shares,
+            };
+            let received = foldhood(
+                vm1,
+                |_vm| Mass {
+                    sum: 0.0,
+                    weight: 0.0,
+                },
+                |a, b| Mass {
+                    sum: a.sum + b.sum,
+                    weight: a.weight + b.weight,
+                },
+                |vm2| {
+                    let nbr_share = nbr(vm2, |_vm| share);
+                    if nbr(vm2, |_vm| active) {
+                        nbr_share
+                    } else {
+                        Mass {
+                            sum: 0.0,
+                            weight: 0.0,
+                        }
+                    }
+                },
+            );
+            if active {
+                received
+            } else {
+                mass
+            }
+        },
+    );
+    mass.sum / mass.weight
+}
+
+/// Spreads the maximum value of the network by gossip. A value is forgotten `ttl` rounds after
+/// the device holding it stops refreshing it, e.g. because it left the network.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `value` - The value of the current device.
+/// * `ttl` - The number of rounds a value survives without being refreshed.
+///
+/// # Returns
+/// The maximum value known to the current device.
+pub fn gossip_max<V>(vm: &mut RoundVM, value: V, ttl: i32) -> V
+where
+    V: Clone + 'static + FromStr + Display + PartialOrd,
+{
+    gossip(vm, value, ttl, |a, b| a > b)
+}
+
+/// Spreads the minimum value of the network by gossip. A value is forgotten `ttl` rounds after
+/// the device holding it stops refreshing it, e.g. because it left the network.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `value` - The value of the current device.
+/// * `ttl` - The number of rounds a value survives without being refreshed.
+///
+/// # Returns
+/// The minimum value known to the current device.
+pub fn gossip_min<V>(vm: &mut RoundVM, value: V, ttl: i32) -> V
+where
+    V: Clone + 'static + FromStr + Display + PartialOrd,
+{
+    gossip(vm, value, ttl, |a, b| a < b)
+}
+
+/// Spreads the best value of the network according to `better`, decreasing the time-to-live of
+/// the values at each hop. Among equal values, the freshest one is kept.
+fn gossip<V, B>(vm: &mut RoundVM, value: V, ttl: i32, better: B) -> V
+where
+    V: Clone + 'static + FromStr + Display + PartialOrd,
+    B: Fn(&V, &V) -> bool + Copy,
+{
+    let local = Rumour {
+        ttl,
+        value: value.clone(),
+    };
+    rep(
+        vm,
+        |_vm| local.clone(),
+        |vm1, rumour| {
+            foldhood(
+                vm1,
+                |_vm| local.clone(),
+                |a, b| {
+                    if better(&b.value, &a.value) || (b.value == a.value && b.ttl > a.ttl) {
+                        b
+                    } else {
+                        a
+                    }
+                },
+                |vm2| {
+                    let nbr_rumour = nbr(vm2, |_vm| rumour.clone());
+                    if nbr_rumour.ttl > 0 {
+                        Rumour {
+                            ttl: nbr_rumour.ttl - 1,
+                            value: nbr_rumour.value,
+                        }
+                    } else {
+                        local.clone()
+                    }
+                },
+            )
+        },
+    )
+    .value
+}
@@ -0,0 +1,380 @@
+use crate::blocks::Tagged;
+use rf_core::lang::builtins::{delta_time, foldhood_plus, mux, nbr_lag};
+use rf_core::lang::{foldhood, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The speed, in distance units per second, at which [crf_gradient] raises its value when no
+/// neighbour supports it.
+pub const DEFAULT_RAISING_SPEED: f64 = 5.0;
+
+/// Compute the gradient of a source with the Constraint and Restoring Force (CRF) algorithm, using
+/// [DEFAULT_RAISING_SPEED] as raising speed.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source has ever been reached.
+pub fn crf_gradient<M>(vm: &mut RoundVM, source: bool, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    crf_gradient_with(vm, source, DEFAULT_RAISING_SPEED, metric)
+}
+
+/// Compute the gradient of a source with the Constraint and Restoring Force (CRF) algorithm.
+/// A device keeps the estimate of the neighbours that still constrain it, otherwise it raises its
+/// own value at a fixed speed, which avoids the slow count-to-infinity of [crate::generalized_gradient]
+/// when a source disappears. Requires the [delta_time] and [nbr_lag] round-time sensors.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `raising_speed` - The speed, in distance units per second, of the value of unconstrained devices.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source has ever been reached.
+pub fn crf_gradient_with<M>(vm: &mut RoundVM, source: bool, raising_speed: f64, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    rep(
+        vm,
+        |_| Tagged::new(f64::INFINITY, 0.0),
+        |vm1, prev| {
+            let dt = delta_time(vm1);
+            // The values of the neighbours date back to their previous round, roughly `dt` before
+            // their export was sent
+            let constraint = foldhood_plus(
+                vm1,
+                |_vm| f64::INFINITY,
+                |a, b| a.min(b),
+                |vm2| {
+                    let nbr_distance = nbr(vm2, |_vm| prev.distance);
+                    let candidate = nbr_distance + metric(vm2);
+                    if candidate + prev.value * (nbr_lag(vm2) + dt) <= prev.distance {
+                        candidate
+                    } else {
+                        f64::INFINITY
+                    }
+                },
+            );
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| Tagged::new(0.0, 0.0),
+                |_vm| {
+                    if constraint.is_finite() {
+                        Tagged::new(constraint, 0.0)
+                    } else {
+                        Tagged::new(prev.distance + raising_speed * dt, raising_speed)
+                    }
+                },
+            )
+        },
+    )
+    .distance
+}
+
+/// Compute the gradient of a source with the Bounded Information Speed (BIS) algorithm, estimating
+/// the speed of information as the lowest ratio between the `metric` and the [nbr_lag] of any link
+/// in the network. Since information crosses each link at least that fast, the estimate never
+/// makes a device farther than it is, whichever path the information takes.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source has ever been reached.
+pub fn bis_gradient<M>(vm: &mut RoundVM, source: bool, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let lag_speed = |vm1: &mut RoundVM| {
+        let lag = nbr_lag(vm1);
+        (lag > 0.0).then(|| metric(vm1) / lag)
+    };
+    let slowest_link = foldhood_plus(
+        vm,
+        |_vm| f64::INFINITY,
+        |a, b| a.min(b),
+        |vm1| lag_speed(vm1).unwrap_or(f64::INFINITY),
+    );
+    // The slowest link of the network is gossiped to every device
+    let slowest = rep(
+        vm,
+        |_vm| f64::INFINITY,
+        |vm1, slowest| {
+            foldhood_plus(
+                vm1,
+                |_vm| slowest_link,
+                |a, b| a.min(b),
+                |vm2| nbr(vm2, |_vm| slowest),
+            )
+        },
+    );
+    let speed = if slowest.is_finite() { slowest } else { 0.0 };
+    bis_gradient_with(vm, source, speed, metric)
+}
+
+/// Compute the gradient of a source with the Bounded Information Speed (BIS) algorithm.
+/// Along with the distance, each device estimates how long ago the information it relies on left
+/// the source: since information cannot travel faster than `speed`, the elapsed time bounds the
+/// distance from below, which makes the gradient rise at `speed` when a source disappears.
+/// Requires the [nbr_lag] round-time sensor.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `speed` - The speed, in distance units per second, at which information travels across the network.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source has ever been reached.
+pub fn bis_gradient_with<M>(vm: &mut RoundVM, source: bool, speed: f64, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    // The distance is paired with the time elapsed since the information left the source
+    rep(
+        vm,
+        |_| Tagged::new(f64::INFINITY, f64::INFINITY),
+        |vm1, prev| {
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| Tagged::new(0.0, 0.0),
+                |vm2| {
+                    foldhood_plus(
+                        vm2,
+                        |_vm| Tagged::new(f64::INFINITY, f64::INFINITY),
+                        |a, b| if b.distance < a.distance { b } else { a },
+                        |vm3| {
+                            let nbr_distance = nbr(vm3, |_vm| prev.distance);
+                            let nbr_time = nbr(vm3, |_vm| prev.value);
+                            let time = nbr_time + nbr_lag(vm3);
+                            let distance = (nbr_distance + metric(vm3)).max(speed * time);
+                            Tagged::new(distance, time)
+                        },
+                    )
+                },
+            )
+        },
+    )
+    .distance
+}
+
+/// The slope of the gradient towards a neighbour, together with what is needed to correct it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Slope {
+    slope: f64,
+    nbr_distance: f64,
+    metric: f64,
+}
+
+impl Display for Slope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{};{}", self.slope, self.nbr_distance, self.metric)
+    }
+}
+
+impl FromStr for Slope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(';').map(|part| part.parse::<f64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(slope)), Some(Ok(nbr_distance)), Some(Ok(metric)), None) => Ok(Slope {
+                slope,
+                nbr_distance,
+                metric,
+            }),
+            _ => Err(format!("Cannot parse slope: {}", s)),
+        }
+    }
+}
+
+/// Compute the gradient of a source with the Flex algorithm, which trades precision for stability:
+/// a device changes its value only when the slope towards its neighbours leaves the tolerance
+/// `1 ± epsilon`, or when its value is way larger than the one suggested by its neighbours.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `epsilon` - The tolerance on the slope of the gradient.
+/// * `delta` - The fraction of `radius` below which the distance from a neighbour is not trusted.
+/// * `radius` - The communication radius of the devices.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, within the tolerance, or `f64::INFINITY` if no source is reachable.
+pub fn flex_gradient<M>(
+    vm: &mut RoundVM,
+    source: bool,
+    epsilon: f64,
+    delta: f64,
+    radius: f64,
+    metric: M,
+) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let distance = move |vm: &mut RoundVM| metric(vm).max(delta * radius);
+    rep(
+        vm,
+        |_| f64::INFINITY,
+        |vm1, g| {
+            let max_slope = foldhood(
+                vm1,
+                |_vm| Slope {
+                    slope: f64::NEG_INFINITY,
+                    nbr_distance: f64::INFINITY,
+                    metric: 0.0,
+                },
+                |a, b| if b.slope > a.slope { b } else { a },
+                |vm2| {
+                    let nbr_distance = nbr(vm2, |_vm| g);
+                    let metric = distance(vm2);
+                    Slope {
+                        slope: (g - nbr_distance) / metric,
+                        nbr_distance,
+                        metric,
+                    }
+                },
+            );
+            let constraint = foldhood_plus(
+                vm1,
+                |_vm| f64::INFINITY,
+                |a, b| a.min(b),
+                |vm2| nbr(vm2, |_vm| g) + distance(vm2),
+            );
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| 0.0,
+                |_vm| {
+                    if radius.max(2.0 * constraint) < g {
+                        constraint
+                    } else if max_slope.slope > 1.0 + epsilon {
+                        max_slope.nbr_distance + (1.0 + epsilon) * max_slope.metric
+                    } else if max_slope.slope < 1.0 - epsilon {
+                        max_slope.nbr_distance + (1.0 - epsilon) * max_slope.metric
+                    } else {
+                        g
+                    }
+                },
+            )
+        },
+    )
+}
+
+/// Compute the gradient of a source with the ULT algorithm, which replicates the gradient in time:
+/// a new replica is started every `period` seconds and only the `replicas` most recent ones are
+/// kept, so that stale information is discarded after at most `replicas * period` seconds.
+/// The value of the device is the minimum among the ones of the replicas. Requires the
+/// [delta_time] round-time sensor.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `period` - The time, in seconds, between the start of two consecutive replicas.
+/// * `replicas` - The number of replicas running at the same time.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source is reachable.
+pub fn ult_gradient<M>(
+    vm: &mut RoundVM,
+    source: bool,
+    period: f64,
+    replicas: usize,
+    metric: M,
+) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    replicated(vm, period, replicas, |vm1, id| {
+        replica(vm1, source, id, metric)
+    })
+    .into_iter()
+    .fold(f64::INFINITY, f64::min)
+}
+
+/// Runs `replicas` replicas of a computation, starting a new one every `period` seconds in place
+/// of the oldest one, and returns the value of each replica. Each replica is given its id, which
+/// changes whenever the replica is restarted. Requires the [delta_time] round-time sensor.
+pub(crate) fn replicated<V, F>(vm: &mut RoundVM, period: f64, replicas: usize, replica: F) -> Vec<V>
+where
+    F: Fn(&mut RoundVM, i32) -> V,
+{
+    let replicas = replicas.max(1) as i32;
+    let newest = (shared_clock(vm) / period).floor() as i32;
+    // Each replica runs in the slot given by its id modulo the number of replicas, so that a new
+    // replica takes the place of the oldest one
+    (0..replicas)
+        .map(|slot| {
+            let id = newest - (newest - slot).rem_euclid(replicas);
+            replica(vm, id)
+        })
+        .collect()
+}
+
+/// Computes the time elapsed since the start of the system, agreed upon by all the devices by
+/// taking the maximum among the neighbours.
+fn shared_clock(vm: &mut RoundVM) -> f64 {
+    let dt = delta_time(vm);
+    rep(
+        vm,
+        |_| 0.0,
+        |vm1, clock| {
+            foldhood(
+                vm1,
+                |_vm| clock,
+                |a: f64, b| a.max(b),
+                |vm2| nbr(vm2, |_vm| clock),
+            ) + dt
+        },
+    )
+}
+
+/// Computes a gradient that restarts from scratch whenever its `id` changes, ignoring the
+/// neighbours running a different replica.
+fn replica<M>(vm: &mut RoundVM, source: bool, id: i32, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    rep(
+        vm,
+        |_| Tagged::new(f64::INFINITY, id),
+        |vm1, prev| {
+            let distance = foldhood_plus(
+                vm1,
+                |_vm| f64::INFINITY,
+                |a, b| a.min(b),
+                |vm2| {
+                    let nbr_distance = nbr(vm2, |_vm| prev.distance);
+                    let nbr_id = nbr(vm2, |_vm| prev.value);
+                    if nbr_id == id {
+                        nbr_distance + metric(vm2)
+                    } else {
+                        f64::INFINITY
+                    }
+                },
+            );
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| Tagged::new(0.0, id),
+                |_vm| Tagged::new(distance, id),
+            )
+        },
+    )
+    .distance
+}
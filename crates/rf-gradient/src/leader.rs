@@ -0,0 +1,218 @@
+use crate::blocks::broadcast;
+use crate::generalized_gradient;
+use rf_core::lang::builtins::next_random;
+use rf_core::lang::{foldhood, mid, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The strategy used to break the symmetry between devices competing for leadership.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymmetryBreaking {
+    /// Each device draws a random priority once, the lowest one wins.
+    Random,
+    /// The device with the lowest id wins.
+    Id,
+}
+
+/// The outcome of a leader election on a device.
+///
+/// * `is_leader` - Whether the device has been elected as leader.
+/// * `leader_id` - The id of the leader of the region the device belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Leader {
+    pub is_leader: bool,
+    pub leader_id: i32,
+}
+
+impl Display for Leader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.is_leader, self.leader_id)
+    }
+}
+
+impl FromStr for Leader {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (is_leader, leader_id) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Leader {
+            is_leader: is_leader.parse::<bool>().map_err(|e| e.to_string())?,
+            leader_id: leader_id.parse::<i32>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// The identifier used by a device when competing for leadership: lower values win.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct Uid {
+    priority: f64,
+    id: i32,
+}
+
+impl Display for Uid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.priority, self.id)
+    }
+}
+
+impl FromStr for Uid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (priority, id) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Uid {
+            priority: priority.parse::<f64>().map_err(|e| e.to_string())?,
+            id: id.parse::<i32>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// Elects a set of leaders such that every device is at most `grain` away from one of them, using
+/// a random priority to break the symmetry between candidates.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of the region each leader is responsible for.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// `true` if the current device is a leader, `false` otherwise.
+pub fn sparse_choice<M>(vm: &mut RoundVM, grain: f64, metric: M) -> bool
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    sparse_choice_with(vm, grain, SymmetryBreaking::Random, metric)
+}
+
+/// Elects a set of leaders such that every device is at most `grain` away from one of them.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of the region each leader is responsible for.
+/// * `symmetry` - The strategy used to break the symmetry between candidates.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// `true` if the current device is a leader, `false` otherwise.
+pub fn sparse_choice_with<M>(
+    vm: &mut RoundVM,
+    grain: f64,
+    symmetry: SymmetryBreaking,
+    metric: M,
+) -> bool
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let uid = rep(
+        vm,
+        |vm1| match symmetry {
+            SymmetryBreaking::Random => Uid {
+                priority: next_random(vm1),
+                id: mid(vm1),
+            },
+            SymmetryBreaking::Id => Uid {
+                priority: 0.0,
+                id: mid(vm1),
+            },
+        },
+        |_vm, uid| uid,
+    );
+    let lead = rep(
+        vm,
+        |_vm| uid,
+        |vm1, lead| {
+            let distance = generalized_gradient(vm1, uid == lead, metric);
+            distance_competition(vm1, distance, lead, uid, grain, metric)
+        },
+    );
+    uid == lead
+}
+
+/// Elects a set of leaders partitioning the network in regions of radius `grain`, using a random
+/// priority to break the symmetry between candidates.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of the region each leader is responsible for.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The [Leader] outcome for the current device.
+pub fn elect_leaders<M>(vm: &mut RoundVM, grain: f64, metric: M) -> Leader
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    elect_leaders_with(vm, grain, SymmetryBreaking::Random, metric)
+}
+
+/// Elects a set of leaders partitioning the network in regions of radius `grain`. Every device
+/// joins the region of the closest leader.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of the region each leader is responsible for.
+/// * `symmetry` - The strategy used to break the symmetry between candidates.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The [Leader] outcome for the current device.
+pub fn elect_leaders_with<M>(
+    vm: &mut RoundVM,
+    grain: f64,
+    symmetry: SymmetryBreaking,
+    metric: M,
+) -> Leader
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let is_leader = sparse_choice_with(vm, grain, symmetry, metric);
+    let self_id = mid(vm);
+    let leader_id = broadcast(vm, is_leader, self_id, metric);
+    Leader {
+        is_leader,
+        leader_id,
+    }
+}
+
+/// Resolves the competition between the candidates in the neighbourhood: a device farther than
+/// `grain` from its current leader becomes a candidate itself, while a device in the border area
+/// does not support any candidate.
+fn distance_competition<M>(
+    vm: &mut RoundVM,
+    distance: f64,
+    lead: Uid,
+    uid: Uid,
+    grain: f64,
+    metric: M,
+) -> Uid
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let inf = Uid {
+        priority: f64::INFINITY,
+        id: uid.id,
+    };
+    let closest = foldhood(
+        vm,
+        |_vm| inf,
+        |a, b| if b < a { b } else { a },
+        |vm1| {
+            let nbr_distance = nbr(vm1, |_vm| distance) + metric(vm1);
+            let nbr_inf = nbr(vm1, |_vm| inf);
+            let nbr_lead = nbr(vm1, |_vm| lead);
+            if nbr_distance >= 0.5 * grain {
+                nbr_inf
+            } else {
+                nbr_lead
+            }
+        },
+    );
+    if distance > grain {
+        uid
+    } else if distance >= 0.5 * grain {
+        inf
+    } else {
+        closest
+    }
+}
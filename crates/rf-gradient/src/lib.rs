@@ -3,6 +3,21 @@ use rf_core::lang::{nbr, rep};
 use rf_core::sensor_id::sensor;
 use rf_core::vm::round_vm::RoundVM;
 
+pub mod allocation;
+pub mod blocks;
+pub mod boundary;
+pub mod channel;
+pub mod collect;
+pub mod colouring;
+pub mod flocking;
+pub mod gossip;
+pub mod gradients;
+pub mod leader;
+pub mod partition;
+pub mod routing;
+pub mod size;
+pub mod tree;
+
 /// Compute the gradient of a source.
 /// N.B. The source must be present in the local [Context] by setting the "source" [Sensor] to true.
 /// # Arguments:
@@ -11,7 +26,7 @@ use rf_core::vm::round_vm::RoundVM;
 /// * `(RoundVM, f64)` - A tuple with the RoundVM after the gradient has been computed and the distance from the source.
 pub fn gradient(vm: &mut RoundVM) -> f64 {
     fn is_source(vm: &mut RoundVM) -> bool {
-        vm.local_sense::<bool>(&sensor("source")).unwrap().clone()
+        *vm.local_sense::<bool>(&sensor("source")).unwrap()
     }
 
     rep(
@@ -34,3 +49,37 @@ pub fn gradient(vm: &mut RoundVM) -> f64 {
         },
     )
 }
+
+/// Compute the gradient of a source, using the given metric to estimate the distance from each neighbour.
+///
+/// # Arguments
+/// * `vm` - The RoundVM to compute the gradient on.
+/// * `source` - Whether the current device is a source.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The distance from the closest source, or `f64::INFINITY` if no source is reachable.
+pub fn generalized_gradient<M>(vm: &mut RoundVM, source: bool, metric: M) -> f64
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    rep(
+        vm,
+        |_| f64::INFINITY,
+        |vm1, d| {
+            mux(
+                vm1,
+                |_vm| source,
+                |_vm| 0.0,
+                |vm2| {
+                    foldhood_plus(
+                        vm2,
+                        |_vm| f64::INFINITY,
+                        |a, b| a.min(b),
+                        |vm3| nbr(vm3, |_vm| d) + metric(vm3),
+                    )
+                },
+            )
+        },
+    )
+}
@@ -0,0 +1,124 @@
+use crate::blocks::broadcast;
+use crate::collect::collect;
+use crate::generalized_gradient;
+use crate::leader::{elect_leaders_with, SymmetryBreaking};
+use rf_core::lang::{align, mid};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The region a device belongs to, identified by its leader.
+///
+/// * `leader_id` - The id of the leader of the region.
+/// * `distance` - The distance of the device from the leader, measured within the region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    pub leader_id: i32,
+    pub distance: f64,
+}
+
+impl Region {
+    /// Whether the given device is the leader of the region.
+    pub fn is_led_by(&self, device: i32) -> bool {
+        self.leader_id == device
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.leader_id, self.distance)
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (leader_id, distance) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Region {
+            leader_id: leader_id.parse::<i32>().map_err(|e| e.to_string())?,
+            distance: distance.parse::<f64>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// Partitions the network in Voronoi-like regions of radius `grain` around elected leaders, using
+/// a random priority to break the symmetry between candidates.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of each region.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The [Region] the current device belongs to.
+pub fn partition<M>(vm: &mut RoundVM, grain: f64, metric: M) -> Region
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    partition_with(vm, grain, SymmetryBreaking::Random, metric)
+}
+
+/// Partitions the network in Voronoi-like regions of radius `grain` around elected leaders. The
+/// distance from the leader is computed within the region, so that regions do not interfere.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `grain` - The radius of each region.
+/// * `symmetry` - The strategy used to break the symmetry between candidates.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The [Region] the current device belongs to.
+pub fn partition_with<M>(
+    vm: &mut RoundVM,
+    grain: f64,
+    symmetry: SymmetryBreaking,
+    metric: M,
+) -> Region
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let leader = elect_leaders_with(vm, grain, symmetry, metric);
+    let distance = align(vm, leader.leader_id, |vm1| {
+        generalized_gradient(vm1, leader.is_leader, metric)
+    });
+    Region {
+        leader_id: leader.leader_id,
+        distance,
+    }
+}
+
+/// Computes a statistic of a region following the Self-organising Coordination Regions (SCR)
+/// pattern: the values of the members are collected at the leader, which broadcasts the result
+/// back to them. Each region is computed in isolation from the others.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `region` - The region of the current device, as computed by [partition].
+/// * `acc` - The function used to accumulate values.
+/// * `local` - The value of the current device.
+/// * `null` - The neutral element of `acc`.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The accumulation of the values of all the members of the region.
+pub fn region_summary<V, A, M>(
+    vm: &mut RoundVM,
+    region: Region,
+    acc: A,
+    local: V,
+    null: V,
+    metric: M,
+) -> V
+where
+    V: Clone + 'static + FromStr + Display,
+    A: Fn(V, V) -> V + Copy,
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let is_leader = region.is_led_by(mid(vm));
+    align(vm, region.leader_id, |vm1| {
+        let collected = collect(vm1, region.distance, acc, local.clone(), null.clone());
+        broadcast(vm1, is_leader, collected, metric)
+    })
+}
@@ -0,0 +1,238 @@
+use crate::collect::find_parent;
+use crate::generalized_gradient;
+use rf_core::lang::{foldhood, mid, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A payload travelling towards a target device. A packet is identified by the device that sent
+/// it and by a sequence number assigned by that device.
+///
+/// The textual representation of the payload must not contain the `|` and `#` characters, which
+/// are used to separate packets in exports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Packet<V> {
+    pub origin: i32,
+    pub sequence: i32,
+    pub payload: V,
+}
+
+impl<V> Packet<V> {
+    fn id(&self) -> PacketId {
+        PacketId {
+            origin: self.origin,
+            sequence: self.sequence,
+        }
+    }
+}
+
+impl<V: Display> Display for Packet<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{};{}", self.origin, self.sequence, self.payload)
+    }
+}
+
+impl<V: FromStr> FromStr for Packet<V> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.splitn(3, ';');
+        let mut next = || fields.next().ok_or("Missing separator");
+        Ok(Packet {
+            origin: next()?.parse::<i32>().map_err(|e| e.to_string())?,
+            sequence: next()?.parse::<i32>().map_err(|e| e.to_string())?,
+            payload: next()?.parse::<V>().map_err(|_| "Cannot parse payload")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PacketId {
+    origin: i32,
+    sequence: i32,
+}
+
+impl Display for PacketId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.origin, self.sequence)
+    }
+}
+
+impl FromStr for PacketId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (origin, sequence) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(PacketId {
+            origin: origin.parse::<i32>().map_err(|e| e.to_string())?,
+            sequence: sequence.parse::<i32>().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+/// The packets held by a device together with the acknowledgements of the packets offered to it
+/// by its neighbours.
+#[derive(Debug, Clone, PartialEq)]
+struct Traffic<V> {
+    packets: Vec<Packet<V>>,
+    acks: Vec<PacketId>,
+}
+
+impl<V> Traffic<V> {
+    fn empty() -> Self {
+        Traffic {
+            packets: vec![],
+            acks: vec![],
+        }
+    }
+
+    fn merge(mut self, other: Traffic<V>) -> Self {
+        self.packets.extend(other.packets);
+        self.acks.extend(other.acks);
+        self
+    }
+}
+
+fn join<T: Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join("|")
+}
+
+fn split<T: FromStr>(s: &str) -> Result<Vec<T>, String> {
+    s.split('|')
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse::<T>()
+                .map_err(|_| "Cannot parse item".to_string())
+        })
+        .collect()
+}
+
+impl<V: Display> Display for Traffic<V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}", join(&self.packets), join(&self.acks))
+    }
+}
+
+impl<V: FromStr> FromStr for Traffic<V> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (packets, acks) = s.split_once('#').ok_or("Missing separator")?;
+        Ok(Traffic {
+            packets: split(packets)?,
+            acks: split(acks)?,
+        })
+    }
+}
+
+/// Computes the next hop towards the target device, i.e. the neighbour with the lowest distance
+/// from the target.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `target` - The id of the target device.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The id of the next hop, or `i32::MAX` if the current device is the target or cannot reach it.
+pub fn next_hop<M>(vm: &mut RoundVM, target: i32, metric: M) -> i32
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let is_target = mid(vm) == target;
+    let distance = generalized_gradient(vm, is_target, metric);
+    find_parent(vm, distance)
+}
+
+/// Routes payloads hop by hop towards the target device. Each device offers the packets it holds
+/// to its next hop through its export, and forgets them once the next hop acknowledges them.
+///
+/// Delivery is at-least-once: a packet may be delivered more than once if the next hop of the
+/// device holding it changes while it is being handed over.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `target` - The id of the target device.
+/// * `outgoing` - The payloads the current device starts sending in this round. Each of them is
+///   sent once, so it must not be provided again in the following rounds.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The payloads delivered to the current device in this round, which are always empty if it is
+/// not the target.
+pub fn route<V, M>(vm: &mut RoundVM, target: i32, outgoing: Vec<V>, metric: M) -> Vec<V>
+where
+    V: Clone + 'static + FromStr + Display,
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let self_id = mid(vm);
+    let hop = next_hop(vm, target, metric);
+    let sent = outgoing.len() as i32;
+    let sequence = rep(vm, |_vm| 0, |_vm, n| n + sent) - sent;
+    let outgoing: Vec<Packet<V>> = outgoing
+        .into_iter()
+        .zip(sequence..)
+        .map(|(payload, sequence)| Packet {
+            origin: self_id,
+            sequence,
+            payload,
+        })
+        .collect();
+    let traffic = rep(
+        vm,
+        |_vm| Traffic::empty(),
+        |vm1, traffic| {
+            let received = foldhood(
+                vm1,
+                |_vm| Traffic::empty(),
+                |a, b| a.merge(b),
+                |vm2| {
+                    let nbr_id = nbr(vm2, mid);
+                    let nbr_hop = nbr(vm2, |_vm| hop);
+                    let nbr_traffic = nbr(vm2, |_vm| traffic.clone());
+                    Traffic {
+                        packets: if nbr_hop == self_id && nbr_id != self_id {
+                            nbr_traffic.packets
+                        } else {
+                            vec![]
+                        },
+                        acks: if nbr_id == hop {
+                            nbr_traffic.acks
+                        } else {
+                            vec![]
+                        },
+                    }
+                },
+            );
+            // The packets already acknowledged are still offered until the sender sees the ack
+            let accepted = received
+                .packets
+                .iter()
+                .filter(|packet| !traffic.acks.contains(&packet.id()))
+                .cloned();
+            let acks = received.packets.iter().map(Packet::id).collect();
+            // The target holds only the packets delivered in the current round
+            let kept = traffic
+                .packets
+                .into_iter()
+                .filter(|packet| self_id != target && !received.acks.contains(&packet.id()));
+            Traffic {
+                packets: kept.chain(outgoing.clone()).chain(accepted).collect(),
+                acks,
+            }
+        },
+    );
+    if self_id == target {
+        traffic
+            .packets
+            .into_iter()
+            .map(|packet| packet.payload)
+            .collect()
+    } else {
+        vec![]
+    }
+}
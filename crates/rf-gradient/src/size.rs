@@ -0,0 +1,237 @@
+use crate::gradients::replicated;
+use crate::leader::SymmetryBreaking;
+use crate::partition::{partition_with, region_summary};
+use rf_core::lang::{foldhood, mid, nbr, rep};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The smallest precision supported by [HyperLogLog].
+pub const MIN_PRECISION: u32 = 4;
+/// The largest precision supported by [HyperLogLog].
+pub const MAX_PRECISION: u32 = 16;
+
+/// A HyperLogLog sketch estimating the number of distinct devices that have been inserted into
+/// it. Sketches are merged by taking the maximum of each register, so that merging is idempotent
+/// and the sketch can be flooded through the network.
+///
+/// A sketch of precision `p` holds `2^p` registers, and its relative standard error is about
+/// `1.04 / sqrt(2^p)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    /// Panics if `precision` is not between [MIN_PRECISION] and [MAX_PRECISION].
+    pub fn new(precision: u32) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "The precision must be between {} and {}",
+            MIN_PRECISION,
+            MAX_PRECISION
+        );
+        HyperLogLog {
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// The precision of the sketch.
+    pub fn precision(&self) -> u32 {
+        self.registers.len().trailing_zeros()
+    }
+
+    /// Inserts a device in the sketch.
+    pub fn insert(&mut self, device: i32) {
+        let precision = self.precision();
+        let hash = splitmix64(device as u32 as u64);
+        let index = (hash >> (64 - precision)) as usize;
+        let rank = ((hash << precision).leading_zeros() + 1).min(64 - precision + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Merges two sketches of the same precision, obtaining the sketch of the union of their
+    /// devices.
+    ///
+    /// # Panics
+    /// Panics if the sketches have different precisions.
+    pub fn merge(&self, other: &HyperLogLog) -> HyperLogLog {
+        assert_eq!(
+            self.registers.len(),
+            other.registers.len(),
+            "Cannot merge sketches with different precisions"
+        );
+        HyperLogLog {
+            registers: self
+                .registers
+                .iter()
+                .zip(other.registers.iter())
+                .map(|(a, b)| *a.max(b))
+                .collect(),
+        }
+    }
+
+    /// Estimates the number of distinct devices inserted in the sketch. Small cardinalities are
+    /// estimated with linear counting, which is much more accurate in that range.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+impl Display for HyperLogLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.registers
+            .iter()
+            .try_for_each(|register| write!(f, "{:02x}", register))
+    }
+}
+
+impl FromStr for HyperLogLog {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || !s.len().is_multiple_of(2) {
+            return Err("Invalid registers".to_string());
+        }
+        let registers = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<u8>, String>>()?;
+        let len = registers.len();
+        if !len.is_power_of_two()
+            || !(MIN_PRECISION..=MAX_PRECISION).contains(&len.trailing_zeros())
+        {
+            return Err("Invalid number of registers".to_string());
+        }
+        Ok(HyperLogLog { registers })
+    }
+}
+
+/// A sketch computed by a replica of [estimate_devices], tagged with the id of the replica.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Replica {
+    id: i32,
+    sketch: HyperLogLog,
+}
+
+impl Display for Replica {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{};{}", self.id, self.sketch)
+    }
+}
+
+impl FromStr for Replica {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, sketch) = s.split_once(';').ok_or("Missing separator")?;
+        Ok(Replica {
+            id: id.parse::<i32>().map_err(|e| e.to_string())?,
+            sketch: sketch.parse::<HyperLogLog>()?,
+        })
+    }
+}
+
+/// The SplitMix64 mixing function, used to spread device ids uniformly over the registers.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Counts the devices of the network exactly: the device with the lowest id is elected as the
+/// only leader, the devices are counted along a spanning tree towards it and the count is
+/// broadcast back to everyone.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `diameter` - An upper bound of the distance between any two devices. Devices farther than
+///   that from the leader elect a leader of their own, while a larger bound slows down the
+///   recovery from the failure of the leader.
+/// * `metric` - The metric used to estimate the distance from the neighbour currently being folded.
+///
+/// # Returns
+/// The number of devices connected to the current one, itself included.
+pub fn count_devices<M>(vm: &mut RoundVM, diameter: f64, metric: M) -> i32
+where
+    M: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let region = partition_with(vm, diameter, SymmetryBreaking::Id, metric);
+    region_summary(vm, region, |a, b| a + b, 1, 0, metric)
+}
+
+/// Estimates the number of devices of the network without any leader, flooding a [HyperLogLog]
+/// sketch of the device ids. Since a sketch can only grow, it is replicated in time: a new replica
+/// starts from scratch every `period` seconds and only the `replicas` most recent ones are kept,
+/// so that the devices that leave the network stop being counted after at most
+/// `replicas * period` seconds. Requires the [rf_core::lang::builtins::delta_time] round-time
+/// sensor.
+///
+/// The oldest replica must have had the time to flood the network, so `replicas * period` should
+/// be larger than the time needed to cross it.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `precision` - The precision of the sketch, between [MIN_PRECISION] and [MAX_PRECISION].
+/// * `period` - The time, in seconds, between the start of two consecutive replicas.
+/// * `replicas` - The number of replicas running at the same time.
+///
+/// # Returns
+/// The estimate of the number of devices connected to the current one, itself included.
+pub fn estimate_devices(vm: &mut RoundVM, precision: u32, period: f64, replicas: usize) -> f64 {
+    let mut local = HyperLogLog::new(precision);
+    local.insert(mid(vm));
+    replicated(vm, period, replicas, |vm1, id| {
+        sketch_replica(vm1, &local, id)
+    })
+    .iter()
+    .fold(local.clone(), |a, b| a.merge(b))
+    .estimate()
+}
+
+/// Floods the sketch of the replica with the given `id`, ignoring the neighbours running a
+/// different replica.
+fn sketch_replica(vm: &mut RoundVM, local: &HyperLogLog, id: i32) -> HyperLogLog {
+    rep(
+        vm,
+        |_vm| Replica {
+            id,
+            sketch: local.clone(),
+        },
+        |vm1, replica| {
+            let sketch = foldhood(
+                vm1,
+                |_vm| local.clone(),
+                |a, b| a.merge(&b),
+                |vm2| {
+                    let nbr_replica = nbr(vm2, |_vm| replica.clone());
+                    if nbr_replica.id == id {
+                        nbr_replica.sketch
+                    } else {
+                        local.clone()
+                    }
+                },
+            );
+            Replica { id, sketch }
+        },
+    )
+    .sketch
+}
@@ -0,0 +1,100 @@
+use crate::collect::find_parent;
+use crate::generalized_gradient;
+use rf_core::lang::builtins::Ids;
+use rf_core::lang::{foldhood, mid, nbr};
+use rf_core::vm::round_vm::RoundVM;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The position of a device in a spanning tree.
+///
+/// * `parent` - The id of the parent, or `i32::MAX` for the root and for devices that cannot reach it.
+/// * `children` - The ids of the children, sorted in ascending order.
+/// * `depth` - The number of hops from the root, or `i32::MAX` for devices that cannot reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub parent: i32,
+    pub children: Vec<i32>,
+    pub depth: i32,
+}
+
+impl TreeNode {
+    /// Whether the device is the root of the tree.
+    pub fn is_root(&self) -> bool {
+        self.depth == 0
+    }
+
+    /// Whether the device has no children.
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+impl Display for TreeNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{};{};{}",
+            self.parent,
+            self.depth,
+            Ids(self.children.clone())
+        )
+    }
+}
+
+impl FromStr for TreeNode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.splitn(3, ';');
+        let mut next = || fields.next().ok_or("Missing separator");
+        Ok(TreeNode {
+            parent: next()?.parse::<i32>().map_err(|e| e.to_string())?,
+            depth: next()?.parse::<i32>().map_err(|e| e.to_string())?,
+            children: next()?.parse::<Ids>()?.0,
+        })
+    }
+}
+
+/// Builds a spanning tree rooted at the source, where each device picks as parent the neighbour
+/// closest to the root, breaking ties by the lowest id. Since the tree follows a hop-count
+/// gradient, it repairs itself when links or devices change.
+///
+/// # Arguments
+/// * `vm` - The current VM.
+/// * `root` - Whether the current device is the root of the tree.
+///
+/// # Returns
+/// The [TreeNode] of the current device.
+pub fn spanning_tree(vm: &mut RoundVM, root: bool) -> TreeNode {
+    let self_id = mid(vm);
+    let distance = generalized_gradient(vm, root, |_vm| 1.0);
+    let parent = find_parent(vm, distance);
+    let mut children = foldhood(
+        vm,
+        |_vm| Ids(vec![]),
+        |mut a, b| {
+            a.0.extend(b.0);
+            a
+        },
+        |vm1| {
+            let nbr_id = nbr(vm1, mid);
+            if nbr(vm1, |_vm| parent) == self_id && nbr_id != self_id {
+                Ids(vec![nbr_id])
+            } else {
+                Ids(vec![])
+            }
+        },
+    )
+    .0;
+    children.sort();
+    TreeNode {
+        parent,
+        children,
+        depth: if distance.is_finite() {
+            distance as i32
+        } else {
+            i32::MAX
+        },
+    }
+}
@@ -0,0 +1,101 @@
+mod utils;
+
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::allocation::allocate_tasks;
+use utils::{grid_topology, line_topology, nbr_range, run_on_topology, Topology};
+
+/// Allocate the tasks read from the "tasks" local sensor, with the capacity read from the
+/// "capacity" local sensor, returning the winner of each task as "task:winner" pairs.
+fn allocation(vm: &mut RoundVM) -> String {
+    let tasks = vm
+        .local_sense::<Vec<i32>>(&sensor("tasks"))
+        .cloned()
+        .unwrap_or_default();
+    let capacity = vm
+        .local_sense::<f64>(&sensor("capacity"))
+        .cloned()
+        .unwrap_or(1.0);
+    let allocations = allocate_tasks(vm, &tasks, capacity, 10.0, nbr_range);
+    let mut winners: Vec<String> = allocations
+        .iter()
+        .map(|(task, allocation)| format!("{}:{}", task, allocation.winner))
+        .collect();
+    winners.sort();
+    winners.join(",")
+}
+
+fn run(topology: Topology, rounds: usize) -> Topology {
+    let scheduling = topology.scheduling(rounds);
+    run_on_topology(allocation, topology, &scheduling)
+}
+
+#[test]
+fn test_nearest_capable_device_wins() {
+    /* The task is generated by device 3, which cannot perform it: devices 2 and 4 are equally
+     * close, and the tie is broken by the lowest id.
+     *  Topology: [1] -- [2] -- [3] -- [4] -- [5].
+     */
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(3, "tasks", vec![7]);
+    topology.set_local_sensor(3, "capacity", 0.0);
+    let final_topology = run(topology, 15);
+    for (_, winners) in final_topology.results::<String>() {
+        assert_eq!(winners, "7:2");
+    }
+}
+
+#[test]
+fn test_capacity_extends_reach() {
+    // Device 5 is twice as far as device 2, but has more than twice its capacity
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(3, "tasks", vec![7]);
+    topology.set_local_sensor(3, "capacity", 0.0);
+    topology.set_local_sensor(5, "capacity", 2.5);
+    let final_topology = run(topology, 15);
+    assert_eq!(final_topology.results::<String>()[&3], "7:5");
+}
+
+#[test]
+fn test_multiple_tasks() {
+    let mut topology = grid_topology(4, 4);
+    topology.set_local_sensor(1, "tasks", vec![100]);
+    topology.set_local_sensor(16, "tasks", vec![200, 300]);
+    topology.set_local_sensor(16, "capacity", 0.0);
+    let final_topology = run(topology, 20);
+    for (_, winners) in final_topology.results::<String>() {
+        assert_eq!(winners, "100:1,200:12,300:12");
+    }
+}
+
+#[test]
+fn test_reassignment_on_failure() {
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(3, "tasks", vec![7]);
+    topology.set_local_sensor(3, "capacity", 0.0);
+    let mut topology = run(topology, 15);
+    assert_eq!(topology.results::<String>()[&3], "7:2");
+
+    topology.remove_device(2);
+    let final_topology = run(topology, 30);
+    let results = final_topology.results::<String>();
+    for d in 3..=5 {
+        assert_eq!(results[&d], "7:4");
+    }
+    // Device 1 is disconnected from the device generating the task, so it leaves the process
+    assert_eq!(results[&1], "");
+}
+
+#[test]
+fn test_completed_task_is_forgotten() {
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(3, "tasks", vec![7]);
+    let mut topology = run(topology, 15);
+    assert_eq!(topology.results::<String>()[&1], "7:3");
+
+    topology.set_local_sensor(3, "tasks", Vec::<i32>::new());
+    let final_topology = run(topology, 30);
+    for (_, winners) in final_topology.results::<String>() {
+        assert_eq!(winners, "");
+    }
+}
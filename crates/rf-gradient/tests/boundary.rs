@@ -0,0 +1,92 @@
+mod utils;
+
+use rufi_gradient::boundary::{distance_to_border, distance_to_edge, is_border, is_edge};
+use utils::{grid_position, grid_topology, nbr_range, run_on_topology, sense_flag, Topology};
+
+/// Whether the device at the given position of a 5x5 grid is on its outer ring.
+fn on_outer_ring(row: i32, col: i32) -> bool {
+    row == 0 || row == 4 || col == 0 || col == 4
+}
+
+fn setup_area() -> Topology {
+    /* Set up a 5x5 grid where the "inside" predicate holds in the 3x3 square in its centre.
+     */
+    let mut topology = grid_topology(5, 5);
+    for d in 1..=25 {
+        let (row, col) = grid_position(d, 5);
+        topology.set_local_sensor(d, "inside", !on_outer_ring(row, col));
+    }
+    topology
+}
+
+#[test]
+fn test_edge_of_area() {
+    let topology = setup_area();
+    let scheduling = topology.scheduling(5);
+    let final_topology = run_on_topology(
+        |vm| {
+            let inside = sense_flag(vm, "inside");
+            is_edge(vm, inside)
+        },
+        topology,
+        &scheduling,
+    );
+    for (d, edge) in final_topology.results::<bool>() {
+        let (row, col) = grid_position(d, 5);
+        let expected = !on_outer_ring(row, col) && (row, col) != (2, 2);
+        assert_eq!(edge, expected, "device {}", d);
+    }
+}
+
+#[test]
+fn test_distance_to_edge() {
+    let topology = setup_area();
+    let scheduling = topology.scheduling(10);
+    let final_topology = run_on_topology(
+        |vm| {
+            let inside = sense_flag(vm, "inside");
+            distance_to_edge(vm, inside, nbr_range)
+        },
+        topology,
+        &scheduling,
+    );
+    for (d, distance) in final_topology.results::<f64>() {
+        let (row, col) = grid_position(d, 5);
+        let expected = if on_outer_ring(row, col) {
+            f64::INFINITY
+        } else if (row, col) == (2, 2) {
+            1.0
+        } else {
+            0.0
+        };
+        assert_eq!(distance, expected, "device {}", d);
+    }
+}
+
+#[test]
+fn test_border_of_deployment() {
+    let topology = grid_topology(5, 5);
+    let scheduling = topology.scheduling(5);
+    let final_topology =
+        run_on_topology(|vm| is_border(vm, 1.0, 4, nbr_range), topology, &scheduling);
+    for (d, border) in final_topology.results::<bool>() {
+        let (row, col) = grid_position(d, 5);
+        assert_eq!(border, on_outer_ring(row, col), "device {}", d);
+    }
+}
+
+#[test]
+fn test_distance_to_border() {
+    let topology = grid_topology(5, 5);
+    let scheduling = topology.scheduling(10);
+    let final_topology = run_on_topology(
+        |vm| distance_to_border(vm, 1.0, 4, nbr_range),
+        topology,
+        &scheduling,
+    );
+    for (d, distance) in final_topology.results::<f64>() {
+        let (row, col) = grid_position(d, 5);
+        let expected = row.min(col).min(4 - row).min(4 - col);
+        assert_eq!(distance, expected as f64, "device {}", d);
+    }
+}
@@ -0,0 +1,72 @@
+mod utils;
+
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::channel::channel;
+use std::collections::{HashMap, HashSet};
+use utils::{
+    grid_id, grid_position, grid_topology, nbr_range, run_on_topology, sense_flag, Topology,
+};
+
+const ROWS: i32 = 5;
+const COLS: i32 = 5;
+
+fn program(vm: &mut RoundVM) -> bool {
+    let source = sense_flag(vm, "source");
+    let destination = sense_flag(vm, "destination");
+    channel(vm, source, destination, 0.5, nbr_range)
+}
+
+fn setup_grid() -> Topology {
+    /* Set up a 5x5 grid, with the source on the left and the destination on the right of the
+     * middle row.
+     */
+    let mut topology = grid_topology(ROWS, COLS);
+    topology.set_local_sensor(grid_id(2, 0, COLS), "source", true);
+    topology.set_local_sensor(grid_id(2, 4, COLS), "destination", true);
+    topology
+}
+
+fn channel_members(results: &HashMap<i32, bool>) -> HashSet<(i32, i32)> {
+    results
+        .iter()
+        .filter(|(_, in_channel)| **in_channel)
+        .map(|(d, _)| grid_position(*d, COLS))
+        .collect()
+}
+
+#[test]
+fn test_channel_along_shortest_path() {
+    let topology = setup_grid();
+    let scheduling = topology.scheduling(15);
+    let final_topology = run_on_topology(program, topology, &scheduling);
+    let expected: HashSet<(i32, i32)> = (0..COLS).map(|col| (2, col)).collect();
+    assert_eq!(channel_members(&final_topology.results()), expected);
+}
+
+#[test]
+fn test_channel_heals_when_a_device_fails() {
+    let topology = setup_grid();
+    let scheduling = topology.scheduling(15);
+    let mut topology = run_on_topology(program, topology, &scheduling);
+
+    // The device in the middle of the channel fails
+    topology.remove_device(grid_id(2, 2, COLS));
+    let scheduling = topology.scheduling(15);
+    let final_topology = run_on_topology(program, topology, &scheduling);
+
+    // The shortest paths now go around the failed device, through the rows above and below it
+    let expected: HashSet<(i32, i32)> = (1..=3)
+        .flat_map(|row| (0..COLS).map(move |col| (row, col)))
+        .filter(|position| *position != (2, 2))
+        .collect();
+    assert_eq!(channel_members(&final_topology.results()), expected);
+}
+
+#[test]
+fn test_no_channel_without_destination() {
+    let mut topology = grid_topology(ROWS, COLS);
+    topology.set_local_sensor(grid_id(2, 0, COLS), "source", true);
+    let scheduling = topology.scheduling(15);
+    let final_topology = run_on_topology(program, topology, &scheduling);
+    assert!(channel_members(&final_topology.results()).is_empty());
+}
@@ -0,0 +1,143 @@
+mod utils;
+
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::collect::{collect, collect_multi_path, find_parent};
+use rufi_gradient::generalized_gradient;
+use utils::{grid_topology, line_topology, nbr_range, run_on_topology, sense_flag, Topology};
+
+fn potential(vm: &mut RoundVM) -> f64 {
+    let sink = sense_flag(vm, "sink");
+    generalized_gradient(vm, sink, nbr_range)
+}
+
+fn reading(vm: &RoundVM) -> i32 {
+    *vm.local_sense::<i32>(&sensor("reading")).unwrap()
+}
+
+fn setup_line() -> Topology {
+    /* Set up a line topology with the sink in device 1, where each device reads ten times its id.
+     *  Topology: [1] -- [2] -- [3] -- [4] -- [5].
+     */
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(1, "sink", true);
+    for d in 1..=5 {
+        topology.set_local_sensor(d, "reading", d * 10);
+    }
+    topology
+}
+
+#[test]
+fn test_find_parent() {
+    let topology = setup_line();
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            find_parent(vm, p)
+        },
+        topology,
+        &scheduling,
+    );
+    let results = final_topology.results::<i32>();
+    assert_eq!(results[&1], i32::MAX);
+    for d in 2..=5 {
+        assert_eq!(results[&d], d - 1);
+    }
+}
+
+#[test]
+fn test_count() {
+    let topology = setup_line();
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            collect(vm, p, |a, b| a + b, 1, 0)
+        },
+        topology,
+        &scheduling,
+    );
+    let results = final_topology.results::<i32>();
+    // Each device counts the devices in its subtree
+    for d in 1..=5 {
+        assert_eq!(results[&d], 6 - d);
+    }
+}
+
+#[test]
+fn test_sum_and_max() {
+    let topology = setup_line();
+    let scheduling = topology.scheduling(20);
+    let sum_topology = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            let local = reading(vm);
+            collect(vm, p, |a, b| a + b, local, 0)
+        },
+        topology.clone(),
+        &scheduling,
+    );
+    assert_eq!(sum_topology.results::<i32>()[&1], 150);
+
+    let max_topology = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            let local = reading(vm);
+            collect(vm, p, |a: i32, b| a.max(b), local, i32::MIN)
+        },
+        topology,
+        &scheduling,
+    );
+    assert_eq!(max_topology.results::<i32>()[&1], 50);
+}
+
+#[test]
+fn test_count_on_grid() {
+    let mut topology = grid_topology(4, 4);
+    topology.set_local_sensor(1, "sink", true);
+    let scheduling = topology.scheduling(30);
+
+    let single_path = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            collect(vm, p, |a, b| a + b, 1, 0)
+        },
+        topology.clone(),
+        &scheduling,
+    );
+    assert_eq!(single_path.results::<i32>()[&1], 16);
+
+    let multi_path = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            collect_multi_path(vm, p, |a, b| a + b, 1.0, 0.0, |v, w| v * w)
+        },
+        topology,
+        &scheduling,
+    );
+    let results = multi_path.results::<f64>();
+    assert!((results[&1] - 16.0).abs() < 1e-6);
+    // The opposite corner has no device sending values to it
+    assert!((results[&16] - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_max_on_grid_multi_path() {
+    let mut topology = grid_topology(4, 4);
+    topology.set_local_sensor(1, "sink", true);
+    for d in 1..=16 {
+        topology.set_local_sensor(d, "reading", d);
+    }
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(
+        |vm| {
+            let p = potential(vm);
+            let local = reading(vm);
+            collect_multi_path(vm, p, |a: i32, b| a.max(b), local, i32::MIN, |v, _| v)
+        },
+        topology,
+        &scheduling,
+    );
+    assert_eq!(final_topology.results::<i32>()[&1], 16);
+}
@@ -0,0 +1,107 @@
+mod utils;
+
+use rufi_gradient::colouring::{colouring, maximal_independent_set};
+use utils::{grid_topology, neighbours, random_topology, run_on_topology, Topology};
+
+const SEEDS: [u64; 5] = [1, 2, 3, 42, 1234];
+
+fn random_topologies() -> Vec<Topology> {
+    SEEDS
+        .iter()
+        .map(|seed| random_topology(30, 0.3, *seed))
+        .collect()
+}
+
+fn max_degree(topology: &Topology) -> i32 {
+    topology
+        .devices
+        .iter()
+        .map(|d| neighbours(topology, *d).len() as i32)
+        .max()
+        .unwrap()
+}
+
+fn assert_proper_colouring(topology: &Topology) {
+    let colours = topology.results::<i32>();
+    for d in &topology.devices {
+        for nbr in neighbours(topology, *d) {
+            assert_ne!(colours[d], colours[&nbr], "devices {} and {}", d, nbr);
+        }
+        assert!(colours[d] >= 0 && colours[d] <= max_degree(topology));
+    }
+}
+
+fn assert_maximal_independent_set(topology: &Topology) {
+    let selected = topology.results::<bool>();
+    for d in &topology.devices {
+        let nbrs = neighbours(topology, *d);
+        if selected[d] {
+            assert!(nbrs.iter().all(|nbr| !selected[nbr]), "device {}", d);
+        } else {
+            assert!(nbrs.iter().any(|nbr| selected[nbr]), "device {}", d);
+        }
+    }
+}
+
+#[test]
+fn test_colouring_on_random_topologies() {
+    for topology in random_topologies() {
+        let scheduling = topology.scheduling(30);
+        let final_topology = run_on_topology(colouring, topology, &scheduling);
+        assert_proper_colouring(&final_topology);
+    }
+}
+
+#[test]
+fn test_colouring_on_grid() {
+    let topology = grid_topology(5, 5);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(colouring, topology, &scheduling);
+    assert_proper_colouring(&final_topology);
+}
+
+#[test]
+fn test_maximal_independent_set_on_random_topologies() {
+    for topology in random_topologies() {
+        let scheduling = topology.scheduling(30);
+        let final_topology = run_on_topology(maximal_independent_set, topology, &scheduling);
+        assert_maximal_independent_set(&final_topology);
+    }
+}
+
+#[test]
+fn test_seeded_colouring_is_reproducible() {
+    let colour = || {
+        let topology = random_topology(30, 0.3, 42);
+        let scheduling = topology.scheduling(30);
+        run_on_topology(colouring, topology, &scheduling).results::<i32>()
+    };
+    assert_eq!(colour(), colour());
+    let select = || {
+        let topology = random_topology(30, 0.3, 42);
+        let scheduling = topology.scheduling(30);
+        run_on_topology(maximal_independent_set, topology, &scheduling).results::<bool>()
+    };
+    assert_eq!(select(), select());
+}
+
+#[test]
+fn test_maximal_independent_set_repair() {
+    let topology = random_topology(30, 0.3, 7);
+    let scheduling = topology.scheduling(30);
+    let mut topology = run_on_topology(maximal_independent_set, topology, &scheduling);
+    // Removing the selected devices forces their neighbours to join the set
+    let selected: Vec<i32> = topology
+        .results::<bool>()
+        .into_iter()
+        .filter(|(_, selected)| *selected)
+        .map(|(d, _)| d)
+        .take(3)
+        .collect();
+    for d in selected {
+        topology.remove_device(d);
+    }
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(maximal_independent_set, topology, &scheduling);
+    assert_maximal_independent_set(&final_topology);
+}
@@ -0,0 +1,117 @@
+mod utils;
+
+use rf_core::geometry::Vector2D;
+use rf_core::lang::builtins::nbr_vector;
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::flocking::{flock, Flocking};
+use std::collections::HashMap;
+use utils::{line_topology, neighbours, run_on_topology, Topology};
+
+const EPSILON: f64 = 1e-9;
+
+/// Set the "nbr_vector" neighbouring sensor of each device from the given positions.
+fn place(topology: &mut Topology, positions: &[(i32, Vector2D)]) {
+    let positions: HashMap<i32, Vector2D> = positions.iter().cloned().collect();
+    for (d, position) in &positions {
+        let vectors = neighbours(topology, *d)
+            .into_iter()
+            .chain([*d])
+            .map(|nbr| (nbr, positions[&nbr] - *position))
+            .collect();
+        topology.set_nbr_sensor(*d, "nbr_vector", vectors);
+    }
+}
+
+fn run_flocking(topology: Topology, params: Flocking) -> HashMap<i32, Vector2D> {
+    let scheduling = topology.scheduling(3);
+    let program = move |vm: &mut RoundVM| {
+        let velocity = vm
+            .local_sense::<Vector2D>(&sensor("velocity"))
+            .cloned()
+            .unwrap_or_default();
+        flock(vm, velocity, params, nbr_vector)
+    };
+    run_on_topology(program, topology, &scheduling).results::<Vector2D>()
+}
+
+fn only(separation: f64, alignment: f64, cohesion: f64) -> Flocking {
+    Flocking {
+        separation,
+        alignment,
+        cohesion,
+        ..Flocking::default()
+    }
+}
+
+#[test]
+fn test_separation() {
+    let mut topology = line_topology(2);
+    place(
+        &mut topology,
+        &[(1, Vector2D::new(0.0, 0.0)), (2, Vector2D::new(0.5, 0.0))],
+    );
+    let results = run_flocking(topology, only(1.0, 0.0, 0.0));
+    assert!(results[&1].x < 0.0 && results[&1].y.abs() < EPSILON);
+    assert!(results[&2].x > 0.0 && results[&2].y.abs() < EPSILON);
+}
+
+#[test]
+fn test_alignment() {
+    let mut topology = line_topology(3);
+    place(
+        &mut topology,
+        &[
+            (1, Vector2D::new(0.0, 0.0)),
+            (2, Vector2D::new(0.0, 2.0)),
+            (3, Vector2D::new(0.0, 4.0)),
+        ],
+    );
+    topology.set_local_sensor(1, "velocity", Vector2D::new(0.5, 0.0));
+    topology.set_local_sensor(3, "velocity", Vector2D::new(0.5, 0.5));
+    let results = run_flocking(topology, only(0.0, 1.0, 0.0));
+    // Device 2 steers towards the average velocity of its neighbours
+    assert!((results[&2] - Vector2D::new(0.5, 0.25)).norm() < EPSILON);
+}
+
+#[test]
+fn test_cohesion() {
+    let mut topology = line_topology(3);
+    place(
+        &mut topology,
+        &[
+            (1, Vector2D::new(0.0, 0.0)),
+            (2, Vector2D::new(0.0, 2.0)),
+            (3, Vector2D::new(0.0, 3.0)),
+        ],
+    );
+    let results = run_flocking(topology, only(0.0, 0.0, 0.25));
+    // Device 1 steers towards its only neighbour, device 2 towards the midpoint of its neighbours
+    assert!((results[&1] - Vector2D::new(0.0, 0.5)).norm() < EPSILON);
+    assert!((results[&2] - Vector2D::new(0.0, -0.125)).norm() < EPSILON);
+}
+
+#[test]
+fn test_steering_is_limited() {
+    let mut topology = line_topology(2);
+    place(
+        &mut topology,
+        &[(1, Vector2D::new(0.0, 0.0)), (2, Vector2D::new(0.01, 0.0))],
+    );
+    let params = Flocking {
+        max_force: 0.5,
+        ..Flocking::default()
+    };
+    let results = run_flocking(topology, params);
+    assert!((results[&1].norm() - 0.5).abs() < EPSILON);
+    assert!((results[&2].norm() - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn test_isolated_device() {
+    let mut topology = line_topology(1);
+    place(&mut topology, &[(1, Vector2D::new(3.0, 3.0))]);
+    topology.set_local_sensor(1, "velocity", Vector2D::new(1.0, 0.0));
+    let results = run_flocking(topology, Flocking::default());
+    assert_eq!(results[&1], Vector2D::zero());
+}
@@ -0,0 +1,120 @@
+mod utils;
+
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::gossip::{gossip_max, gossip_min, neighbour_average, push_sum};
+use utils::{line_topology, ring_topology, run_on_topology, run_synchronously, Topology};
+
+const TOLERANCE: f64 = 1e-3;
+
+fn temperature(vm: &RoundVM) -> f64 {
+    *vm.local_sense::<f64>(&sensor("temperature")).unwrap()
+}
+
+fn with_temperatures(mut topology: Topology) -> Topology {
+    // Each device reads a temperature of 10 degrees times its id, on average 30 degrees on 5 devices
+    for d in topology.devices.clone() {
+        topology.set_local_sensor(d, "temperature", 10.0 * d as f64);
+    }
+    topology
+}
+
+fn run_average(
+    topology: Topology,
+    average: fn(&mut RoundVM, f64) -> f64,
+    rounds: usize,
+) -> Vec<f64> {
+    let scheduling = topology.scheduling(rounds);
+    let final_topology = run_on_topology(
+        move |vm| {
+            let value = temperature(vm);
+            average(vm, value)
+        },
+        topology,
+        &scheduling,
+    );
+    final_topology.results::<f64>().into_values().collect()
+}
+
+#[test]
+fn test_neighbour_average() {
+    // Devices agree on an estimate biased by the sequential updates, which is the same at every
+    // run since devices always run in order: the exact average is checked on synchronous rounds
+    for (topology, expected) in [(line_topology(5), 535.0 / 19.0), (ring_topology(5), 27.25)] {
+        for estimate in run_average(with_temperatures(topology), neighbour_average, 200) {
+            assert!((estimate - expected).abs() < TOLERANCE, "{}", estimate);
+        }
+    }
+}
+
+#[test]
+fn test_neighbour_average_on_synchronous_rounds() {
+    let program = |vm: &mut RoundVM| {
+        let value = temperature(vm);
+        neighbour_average(vm, value)
+    };
+    for topology in [line_topology(5), ring_topology(5)] {
+        let mut topology = with_temperatures(topology);
+        for _ in 0..100 {
+            topology = run_synchronously(program, topology, 1);
+            let estimates = topology.results::<f64>();
+            // The symmetric weights preserve the sum of the estimates
+            let sum: f64 = estimates.values().sum();
+            assert!((sum - 150.0).abs() < 1e-9, "{}", sum);
+        }
+        for estimate in topology.results::<f64>().values() {
+            assert!((estimate - 30.0).abs() < TOLERANCE, "{}", estimate);
+        }
+    }
+}
+
+#[test]
+fn test_push_sum() {
+    for topology in [line_topology(5), ring_topology(5)] {
+        for estimate in run_average(with_temperatures(topology), push_sum, 200) {
+            assert!((estimate - 30.0).abs() < TOLERANCE, "{}", estimate);
+        }
+    }
+}
+
+#[test]
+fn test_gossip_max_and_min() {
+    for topology in [line_topology(5), ring_topology(5)] {
+        let topology = with_temperatures(topology);
+        let scheduling = topology.scheduling(10);
+        let max = run_on_topology(
+            |vm| {
+                let value = temperature(vm);
+                gossip_max(vm, value, 5)
+            },
+            topology.clone(),
+            &scheduling,
+        );
+        assert!(max.results::<f64>().values().all(|v| *v == 50.0));
+        let min = run_on_topology(
+            |vm| {
+                let value = temperature(vm);
+                gossip_min(vm, value, 5)
+            },
+            topology,
+            &scheduling,
+        );
+        assert!(min.results::<f64>().values().all(|v| *v == 10.0));
+    }
+}
+
+#[test]
+fn test_gossip_forgets_values_after_ttl() {
+    let program = |vm: &mut RoundVM| {
+        let value = temperature(vm);
+        gossip_max(vm, value, 5)
+    };
+    let topology = with_temperatures(ring_topology(5));
+    let scheduling = topology.scheduling(10);
+    let mut topology = run_on_topology(program, topology, &scheduling);
+    // The device holding the maximum leaves the network
+    topology.remove_device(5);
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(program, topology, &scheduling);
+    assert!(final_topology.results::<f64>().values().all(|v| *v == 40.0));
+}
@@ -0,0 +1,260 @@
+mod utils;
+
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::gradients::{
+    bis_gradient, bis_gradient_with, crf_gradient, flex_gradient, ult_gradient,
+    DEFAULT_RAISING_SPEED,
+};
+use rufi_gradient::{generalized_gradient, gradient};
+use std::collections::HashMap;
+use utils::{line_topology, nbr_range, run_on_topology, sense_flag, Topology};
+
+type Gradient = fn(&mut RoundVM, bool, fn(&mut RoundVM) -> f64) -> f64;
+
+const GRADIENTS: [Gradient; 3] = [generalized_gradient, crf_gradient, bis_gradient];
+
+fn setup_test_topology(sources: &[i32]) -> Topology {
+    /* Set up the topology of the gradient tests, where each round lasts one second.
+     *  Topology: [1] -- [2] -- [3] -- [4] -- [5].
+     */
+    let mut topology = line_topology(5);
+    topology.set_round_duration(1.0);
+    for source in sources {
+        topology.set_local_sensor(*source, "source", true);
+    }
+    topology
+}
+
+fn setup_weighted_topology(sources: &[i32]) -> Topology {
+    /* Set up a topology with a short link, over which the classic gradient counts to infinity
+     * slowly, where each round lasts one second.
+     *  Topology: [1] -10- [2] -1- [3] -10- [4] -10- [5].
+     */
+    let mut topology = setup_test_topology(sources);
+    let ranges = [(1, 2, 10), (2, 3, 1), (3, 4, 10), (4, 5, 10)];
+    for d in 1..=5 {
+        let nbr_ranges = ranges
+            .iter()
+            .filter(|(a, b, _)| *a == d || *b == d)
+            .map(|(a, b, range)| (if *a == d { *b } else { *a }, *range))
+            .chain([(d, 0)])
+            .collect();
+        topology.set_nbr_sensor(d, "nbr_range", nbr_ranges);
+    }
+    topology
+}
+
+/// The speed of information in the weighted topology: it takes one second per hop and covers at
+/// least 5.5 units per second along the shortest paths from either end of the line.
+const INFORMATION_SPEED: f64 = 5.0;
+
+fn bis(vm: &mut RoundVM, source: bool, metric: fn(&mut RoundVM) -> f64) -> f64 {
+    bis_gradient_with(vm, source, INFORMATION_SPEED, metric)
+}
+
+fn run(gradient: Gradient, topology: Topology, rounds: usize) -> Topology {
+    let scheduling = topology.scheduling(rounds);
+    run_on_topology(
+        move |vm| {
+            let source = sense_flag(vm, "source");
+            gradient(vm, source, nbr_range)
+        },
+        topology,
+        &scheduling,
+    )
+}
+
+/// Run the gradient one round at a time, returning the number of rounds needed to reach the
+/// expected distances.
+fn rounds_to_converge(
+    gradient: Gradient,
+    mut topology: Topology,
+    expected: &HashMap<i32, f64>,
+) -> Option<usize> {
+    for rounds in 1..=30 {
+        topology = run(gradient, topology, 1);
+        if topology.results::<f64>() == *expected {
+            return Some(rounds);
+        }
+    }
+    None
+}
+
+fn expected(distances: [f64; 5]) -> HashMap<i32, f64> {
+    (1..=5).zip(distances).collect()
+}
+
+#[test]
+fn test_single_source() {
+    for gradient in GRADIENTS {
+        let topology = run(gradient, setup_test_topology(&[1]), 10);
+        assert_eq!(
+            topology.results::<f64>(),
+            expected([0.0, 1.0, 2.0, 3.0, 4.0])
+        );
+    }
+}
+
+#[test]
+fn test_multiple_sources() {
+    for gradient in GRADIENTS {
+        let topology = run(gradient, setup_test_topology(&[1, 5]), 10);
+        assert_eq!(
+            topology.results::<f64>(),
+            expected([0.0, 1.0, 2.0, 1.0, 0.0])
+        );
+    }
+}
+
+#[test]
+fn test_recovery_when_a_source_is_switched_off() {
+    let recovery = |gradient: Gradient| {
+        let mut topology = run(gradient, setup_weighted_topology(&[1, 5]), 10);
+        topology.set_local_sensor(1, "source", false);
+        rounds_to_converge(gradient, topology, &expected([31.0, 21.0, 20.0, 10.0, 0.0]))
+            .expect("the gradient does not recover")
+    };
+    let classic = recovery(generalized_gradient);
+    // Devices 2 and 3 raise each other by the short link only, while CRF devices rise at the
+    // raising speed and BIS devices at the speed of information
+    assert!(recovery(crf_gradient) < classic);
+    assert!(recovery(bis) < classic);
+}
+
+#[test]
+fn test_rising_when_the_only_source_is_switched_off() {
+    let rise = |gradient: Gradient, topology: Topology| {
+        let mut topology = run(gradient, topology, 10);
+        topology.set_local_sensor(1, "source", false);
+        let results = run(gradient, topology, 10).results::<f64>();
+        results.values().cloned().fold(f64::INFINITY, f64::min)
+    };
+    let classic = rise(generalized_gradient, setup_test_topology(&[1]));
+    let crf = rise(crf_gradient, setup_test_topology(&[1]));
+    let bis_estimated = rise(bis_gradient, setup_test_topology(&[1]));
+    // No device believes to be within the diameter of the network anymore
+    assert!(classic > 4.0 && crf > 4.0 && bis_estimated > 4.0);
+    // CRF devices stop relying on their neighbours and rise at the raising speed
+    assert!(crf >= 5.0 * DEFAULT_RAISING_SPEED);
+    assert!(crf > classic);
+    // Over a short link, BIS devices rise at the speed of information instead
+    let classic = rise(generalized_gradient, setup_weighted_topology(&[1]));
+    let bis = rise(bis, setup_weighted_topology(&[1]));
+    assert!(bis >= 2.0 * classic);
+}
+
+/// Run the program until every device rises above the diameter of the line after the only source
+/// is switched off, returning the number of rounds needed.
+fn rounds_to_rise<P>(program: P) -> Option<usize>
+where
+    P: Fn(&mut RoundVM) -> f64 + Copy,
+{
+    let mut topology = setup_test_topology(&[]);
+    for d in 1..=5 {
+        topology.set_local_sensor(d, "source", d == 1);
+    }
+    let scheduling = topology.scheduling(10);
+    topology = run_on_topology(program, topology, &scheduling);
+    topology.set_local_sensor(1, "source", false);
+    let scheduling = topology.scheduling(1);
+    for rounds in 1..=30 {
+        topology = run_on_topology(program, topology, &scheduling);
+        if topology.results::<f64>().values().all(|d| *d > 4.0) {
+            return Some(rounds);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_recovery_in_the_test_topology() {
+    let estimated = |gradient: Gradient| {
+        move |vm: &mut RoundVM| {
+            let source = sense_flag(vm, "source");
+            gradient(vm, source, nbr_range)
+        }
+    };
+    let classic = rounds_to_rise(gradient).expect("the gradient does not rise");
+    let crf = rounds_to_rise(estimated(crf_gradient)).expect("CRF does not rise");
+    let bis = rounds_to_rise(estimated(bis_gradient)).expect("BIS does not rise");
+    assert!(crf < classic);
+    // Every link has the same length and lag, so the slowest link from which BIS estimates the
+    // speed of information is as fast as the classic gradient rises
+    assert!(bis <= classic);
+}
+
+#[test]
+fn test_bis_gradient_estimates_a_safe_speed() {
+    // Device [1] only has a link covering 10 units per second, but the information from [5] crosses
+    // the short link on its way, so a speed estimated from the links of [1] alone would make it
+    // believe to be farther than it is
+    let topology = run(bis_gradient, setup_weighted_topology(&[5]), 10);
+    assert_eq!(
+        topology.results::<f64>(),
+        expected([31.0, 21.0, 20.0, 10.0, 0.0])
+    );
+}
+
+fn flex(vm: &mut RoundVM, source: bool, metric: fn(&mut RoundVM) -> f64) -> f64 {
+    flex_gradient(vm, source, 0.5, 1.0, 1.0, metric)
+}
+
+fn ult(vm: &mut RoundVM, source: bool, metric: fn(&mut RoundVM) -> f64) -> f64 {
+    ult_gradient(vm, source, 5.0, 2, metric)
+}
+
+#[test]
+fn test_flex_and_ult_gradients() {
+    for gradient in [flex as Gradient, ult] {
+        let topology = run(gradient, setup_test_topology(&[1]), 10);
+        assert_eq!(
+            topology.results::<f64>(),
+            expected([0.0, 1.0, 2.0, 3.0, 4.0])
+        );
+        let topology = run(gradient, setup_test_topology(&[1, 5]), 10);
+        assert_eq!(
+            topology.results::<f64>(),
+            expected([0.0, 1.0, 2.0, 1.0, 0.0])
+        );
+    }
+}
+
+#[test]
+fn test_flex_gradient_is_stable_within_tolerance() {
+    fn stretched_range(vm: &mut RoundVM) -> f64 {
+        nbr_range(vm) * 1.2
+    }
+    let run_stretched = |gradient: Gradient| {
+        let topology = run(gradient, setup_test_topology(&[1]), 10);
+        let scheduling = topology.scheduling(10);
+        run_on_topology(
+            move |vm| {
+                let source = sense_flag(vm, "source");
+                gradient(vm, source, stretched_range)
+            },
+            topology,
+            &scheduling,
+        )
+        .results::<f64>()
+    };
+    // The slope towards each neighbour stays within 1 ± 0.5, so no device changes its value
+    assert_eq!(run_stretched(flex), expected([0.0, 1.0, 2.0, 3.0, 4.0]));
+    assert_ne!(
+        run_stretched(generalized_gradient),
+        expected([0.0, 1.0, 2.0, 3.0, 4.0])
+    );
+}
+
+#[test]
+fn test_ult_gradient_discards_stale_replicas() {
+    let mut topology = run(ult, setup_test_topology(&[1]), 10);
+    topology.set_local_sensor(1, "source", false);
+    // Every replica is restarted without a source within two periods
+    let results = run(ult, topology, 11).results::<f64>();
+    assert!(results.values().all(|d| d.is_infinite()));
+
+    let mut topology = run(ult, setup_test_topology(&[1, 5]), 10);
+    topology.set_local_sensor(1, "source", false);
+    let rounds = rounds_to_converge(ult, topology, &expected([4.0, 3.0, 2.0, 1.0, 0.0]));
+    assert!(rounds.is_some_and(|rounds| rounds <= 11));
+}
@@ -0,0 +1,116 @@
+mod utils;
+
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::leader::{elect_leaders, elect_leaders_with, Leader, SymmetryBreaking};
+use std::collections::{HashMap, HashSet};
+use utils::{grid_topology, hop_distances, line_topology, nbr_range, run_on_topology, Topology};
+
+const GRAIN: f64 = 3.0;
+
+fn random_election(vm: &mut RoundVM) -> Leader {
+    elect_leaders(vm, GRAIN, nbr_range)
+}
+
+fn id_election(vm: &mut RoundVM) -> Leader {
+    elect_leaders_with(vm, GRAIN, SymmetryBreaking::Id, nbr_range)
+}
+
+/// Set the "seed" local sensor of every device, so that random elections are reproducible.
+fn seeded(mut topology: Topology, seed: u64) -> Topology {
+    for d in topology.devices.clone() {
+        topology.set_local_sensor(d, "seed", seed);
+    }
+    topology
+}
+
+fn leaders(results: &HashMap<i32, Leader>) -> HashSet<i32> {
+    results
+        .iter()
+        .filter(|(_, leader)| leader.is_leader)
+        .map(|(d, _)| *d)
+        .collect()
+}
+
+/// Assert that each region has exactly one leader and that every device is at most GRAIN away
+/// from the leader of its region.
+fn assert_one_leader_per_region(topology: &Topology, results: &HashMap<i32, Leader>) {
+    let leaders = leaders(results);
+    assert!(!leaders.is_empty());
+    for (d, leader) in results {
+        assert!(leaders.contains(&leader.leader_id));
+        assert_eq!(leader.is_leader, leader.leader_id == *d);
+        let distance = hop_distances(topology, leader.leader_id)[d];
+        assert!(distance as f64 <= GRAIN);
+    }
+    // Leaders do not compete with each other within half of the grain
+    for leader in leaders.iter() {
+        let distances = hop_distances(topology, *leader);
+        for other in leaders.iter().filter(|other| *other != leader) {
+            assert!(distances[other] as f64 >= 0.5 * GRAIN);
+        }
+    }
+}
+
+#[test]
+fn test_id_election_on_line() {
+    let topology = line_topology(10);
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(id_election, topology, &scheduling);
+    let results = final_topology.results::<Leader>();
+    assert_eq!(leaders(&results), HashSet::from([1, 5, 9]));
+    assert_one_leader_per_region(&final_topology, &results);
+}
+
+#[test]
+fn test_random_election_on_line() {
+    let topology = seeded(line_topology(10), 7);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(random_election, topology, &scheduling);
+    let results = final_topology.results::<Leader>();
+    assert_one_leader_per_region(&final_topology, &results);
+}
+
+#[test]
+fn test_id_election_on_grid() {
+    let topology = grid_topology(6, 6);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(id_election, topology, &scheduling);
+    let results = final_topology.results::<Leader>();
+    assert!(leaders(&results).contains(&1));
+    assert_one_leader_per_region(&final_topology, &results);
+}
+
+#[test]
+fn test_random_election_on_grid() {
+    let topology = seeded(grid_topology(6, 6), 7);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(random_election, topology, &scheduling);
+    let results = final_topology.results::<Leader>();
+    assert_one_leader_per_region(&final_topology, &results);
+}
+
+#[test]
+fn test_single_leader_with_large_grain() {
+    let topology = line_topology(5);
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(
+        |vm| elect_leaders(vm, 10.0, nbr_range),
+        topology,
+        &scheduling,
+    );
+    let results = final_topology.results::<Leader>();
+    assert_eq!(leaders(&results).len(), 1);
+    let leader = *leaders(&results).iter().next().unwrap();
+    assert!(results.values().all(|l| l.leader_id == leader));
+}
+
+#[test]
+fn test_seeded_election_is_reproducible() {
+    let elect = || {
+        let topology = seeded(grid_topology(6, 6), 7);
+        let scheduling = topology.scheduling(30);
+        let final_topology = run_on_topology(random_election, topology, &scheduling);
+        leaders(&final_topology.results::<Leader>())
+    };
+    assert_eq!(elect(), elect());
+}
@@ -0,0 +1,106 @@
+mod utils;
+
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::leader::SymmetryBreaking;
+use rufi_gradient::partition::{partition, partition_with, region_summary, Region};
+use std::collections::HashMap;
+use utils::{grid_topology, hop_distances, line_topology, nbr_range, run_on_topology, Topology};
+
+const GRAIN: f64 = 3.0;
+
+fn region(vm: &mut RoundVM) -> Region {
+    partition_with(vm, GRAIN, SymmetryBreaking::Id, nbr_range)
+}
+
+fn members(regions: &HashMap<i32, Region>, leader: i32) -> Vec<i32> {
+    regions
+        .iter()
+        .filter(|(_, region)| region.leader_id == leader)
+        .map(|(d, _)| *d)
+        .collect()
+}
+
+/// Assert that each region is connected around its leader and that the distance of each device is
+/// measured within its region.
+fn assert_regions(topology: &Topology, regions: &HashMap<i32, Region>) {
+    for (d, region) in regions {
+        assert!(regions[&region.leader_id].is_led_by(region.leader_id));
+        let mut isolated = topology.clone();
+        for other in topology.devices.iter() {
+            if regions[other].leader_id != region.leader_id {
+                isolated.remove_device(*other);
+            }
+        }
+        let distance = hop_distances(&isolated, region.leader_id)[d];
+        assert_eq!(region.distance, distance as f64);
+        assert!(region.distance <= GRAIN);
+    }
+}
+
+#[test]
+fn test_partition_on_line() {
+    let topology = line_topology(10);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(region, topology, &scheduling);
+    let regions = final_topology.results::<Region>();
+    assert_regions(&final_topology, &regions);
+    for leader in [1, 5, 9] {
+        assert!(regions[&leader].is_led_by(leader));
+    }
+}
+
+#[test]
+fn test_partition_on_grid() {
+    let topology = grid_topology(6, 6);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(region, topology, &scheduling);
+    assert_regions(&final_topology, &final_topology.results::<Region>());
+
+    let topology = grid_topology(6, 6);
+    let final_topology =
+        run_on_topology(|vm| partition(vm, GRAIN, nbr_range), topology, &scheduling);
+    assert_regions(&final_topology, &final_topology.results::<Region>());
+}
+
+#[test]
+fn test_region_summary() {
+    let mut topology = grid_topology(6, 6);
+    for d in 1..=36 {
+        topology.set_local_sensor(d, "reading", d);
+    }
+    let scheduling = topology.scheduling(40);
+    let regions = run_on_topology(region, topology.clone(), &scheduling).results::<Region>();
+    let count = run_on_topology(
+        |vm| {
+            let region = region(vm);
+            region_summary(vm, region, |a, b| a + b, 1, 0, nbr_range)
+        },
+        topology.clone(),
+        &scheduling,
+    )
+    .results::<i32>();
+    let max = run_on_topology(
+        |vm| {
+            let region = region(vm);
+            let reading = *vm.local_sense::<i32>(&sensor("reading")).unwrap();
+            region_summary(
+                vm,
+                region,
+                |a: i32, b| a.max(b),
+                reading,
+                i32::MIN,
+                nbr_range,
+            )
+        },
+        topology,
+        &scheduling,
+    )
+    .results::<i32>();
+    // Every member of a region knows the statistics of its own region only
+    for (d, region) in regions.iter() {
+        let members = members(&regions, region.leader_id);
+        assert_eq!(count[d], members.len() as i32);
+        assert_eq!(max[d], *members.iter().max().unwrap());
+    }
+}
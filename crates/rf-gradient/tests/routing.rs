@@ -0,0 +1,80 @@
+mod utils;
+
+use rf_core::lang::rep;
+use rf_core::sensor_id::sensor;
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::routing::{next_hop, route};
+use utils::{line_topology, nbr_range, run_on_topology, Topology};
+
+/// Route the payloads read from the "outgoing" local sensor to the given target, returning the
+/// number of payloads delivered so far and their sum.
+fn deliver(vm: &mut RoundVM, target: i32) -> String {
+    let outgoing = vm
+        .local_sense::<Vec<i32>>(&sensor("outgoing"))
+        .cloned()
+        .unwrap_or_default();
+    let delivered = route(vm, target, outgoing, nbr_range);
+    let count = rep(vm, |_vm| 0, |_vm, n| n + delivered.len() as i32);
+    let sum = rep(vm, |_vm| 0, |_vm, s| s + delivered.iter().sum::<i32>());
+    format!("{};{}", count, sum)
+}
+
+/// Run the routing program with each device sending the given payloads in its first round only.
+fn run_routing(
+    mut topology: Topology,
+    target: i32,
+    payloads: &[(i32, Vec<i32>)],
+    rounds: usize,
+) -> Topology {
+    for (d, outgoing) in payloads {
+        topology.set_local_sensor(*d, "outgoing", outgoing.clone());
+    }
+    let scheduling = topology.scheduling(1);
+    let program = move |vm: &mut RoundVM| deliver(vm, target);
+    topology = run_on_topology(program, topology, &scheduling);
+    for (d, _) in payloads {
+        topology.set_local_sensor(*d, "outgoing", Vec::<i32>::new());
+    }
+    let scheduling = topology.scheduling(rounds);
+    run_on_topology(program, topology, &scheduling)
+}
+
+#[test]
+fn test_next_hop() {
+    let topology = line_topology(5);
+    let scheduling = topology.scheduling(10);
+    let final_topology = run_on_topology(|vm| next_hop(vm, 3, nbr_range), topology, &scheduling);
+    let results = final_topology.results::<i32>();
+    assert_eq!(results[&1], 2);
+    assert_eq!(results[&2], 3);
+    assert_eq!(results[&3], i32::MAX);
+    assert_eq!(results[&4], 3);
+    assert_eq!(results[&5], 4);
+}
+
+#[test]
+fn test_route_along_line() {
+    let final_topology = run_routing(line_topology(5), 1, &[(5, vec![10, 20, 30])], 20);
+    let results = final_topology.results::<String>();
+    assert_eq!(results[&1], "3;60");
+    for d in 2..=5 {
+        assert_eq!(results[&d], "0;0");
+    }
+}
+
+#[test]
+fn test_route_from_both_sides() {
+    let payloads = [(1, vec![1, 2]), (5, vec![100]), (4, vec![1000])];
+    let final_topology = run_routing(line_topology(5), 2, &payloads, 20);
+    let results = final_topology.results::<String>();
+    assert_eq!(results[&2], "4;1103");
+}
+
+#[test]
+fn test_route_to_unreachable_target() {
+    // Payloads are held until a path towards the target exists
+    let final_topology = run_routing(line_topology(5), 42, &[(5, vec![10])], 20);
+    for (_, result) in final_topology.results::<String>() {
+        assert_eq!(result, "0;0");
+    }
+}
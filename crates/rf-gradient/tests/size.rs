@@ -0,0 +1,110 @@
+mod utils;
+
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::size::{count_devices, estimate_devices, HyperLogLog};
+use utils::{grid_topology, line_topology, nbr_range, run_on_topology};
+
+#[test]
+fn test_count_devices_on_line() {
+    let topology = line_topology(6);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(
+        |vm| count_devices(vm, 10.0, nbr_range),
+        topology,
+        &scheduling,
+    );
+    for (_, count) in final_topology.results::<i32>() {
+        assert_eq!(count, 6);
+    }
+}
+
+#[test]
+fn test_count_devices_on_grid() {
+    let topology = grid_topology(4, 5);
+    let scheduling = topology.scheduling(40);
+    let final_topology = run_on_topology(
+        |vm| count_devices(vm, 10.0, nbr_range),
+        topology,
+        &scheduling,
+    );
+    for (_, count) in final_topology.results::<i32>() {
+        assert_eq!(count, 20);
+    }
+}
+
+#[test]
+fn test_count_devices_after_failure() {
+    let topology = grid_topology(3, 3);
+    let scheduling = topology.scheduling(30);
+    let mut topology = run_on_topology(
+        |vm| count_devices(vm, 10.0, nbr_range),
+        topology,
+        &scheduling,
+    );
+    // Removing the leader forces a new election
+    topology.remove_device(1);
+    let scheduling = topology.scheduling(40);
+    let final_topology = run_on_topology(
+        |vm| count_devices(vm, 10.0, nbr_range),
+        topology,
+        &scheduling,
+    );
+    for (_, count) in final_topology.results::<i32>() {
+        assert_eq!(count, 8);
+    }
+}
+
+fn estimate(vm: &mut RoundVM) -> f64 {
+    estimate_devices(vm, 10, 10.0, 3)
+}
+
+#[test]
+fn test_estimate_devices() {
+    let mut topology = grid_topology(5, 6);
+    topology.set_round_duration(1.0);
+    let scheduling = topology.scheduling(20);
+    let final_topology = run_on_topology(estimate, topology, &scheduling);
+    let results = final_topology.results::<f64>();
+    let estimate = results[&1];
+    assert!((estimate - 30.0).abs() < 3.0, "estimate: {}", estimate);
+    // Every device agrees on the same estimate
+    for (_, value) in results {
+        assert_eq!(value, estimate);
+    }
+}
+
+#[test]
+fn test_estimate_devices_after_removal() {
+    let mut topology = grid_topology(5, 6);
+    topology.set_round_duration(1.0);
+    let scheduling = topology.scheduling(20);
+    let mut topology = run_on_topology(estimate, topology, &scheduling);
+    // The last two rows leave the network
+    (19..=30).for_each(|d| topology.remove_device(d));
+    // Until the replicas that counted them expire, they are still counted
+    let scheduling = topology.scheduling(5);
+    let topology = run_on_topology(estimate, topology, &scheduling);
+    assert!(topology.results::<f64>()[&1] > 24.0);
+    let scheduling = topology.scheduling(30);
+    let final_topology = run_on_topology(estimate, topology, &scheduling);
+    for (_, estimate) in final_topology.results::<f64>() {
+        assert!((estimate - 18.0).abs() < 2.0, "estimate: {}", estimate);
+    }
+}
+
+#[test]
+fn test_hyper_log_log() {
+    let mut sketch = HyperLogLog::new(12);
+    (1..=1000).for_each(|d| sketch.insert(d));
+    assert!((sketch.estimate() - 1000.0).abs() < 50.0);
+
+    let mut left = HyperLogLog::new(12);
+    let mut right = HyperLogLog::new(12);
+    (1..=600).for_each(|d| left.insert(d));
+    (400..=1000).for_each(|d| right.insert(d));
+    assert_eq!(left.merge(&right), sketch);
+
+    let parsed: HyperLogLog = sketch.to_string().parse().unwrap();
+    assert_eq!(parsed, sketch);
+    assert!("abc".parse::<HyperLogLog>().is_err());
+}
@@ -1,20 +1,25 @@
+// The tests predate the clippy configuration of the workspace and are kept as they were written.
+#![allow(
+    unused_parens,
+    clippy::clone_on_copy,
+    clippy::manual_repeat_n,
+    clippy::needless_borrow,
+    clippy::to_string_in_format_args
+)]
+
 mod utils;
 
-use rf_core::context::Context;
 use rf_core::export::Export;
-use rf_core::lang::execution::round;
 use rf_core::path::Path;
 use rf_core::sensor_id::{sensor, SensorId};
 use rf_core::slot::Slot::{FoldHood, Nbr, Rep};
-use rf_core::vm::round_vm::RoundVM;
 use rf_core::{export, path};
 use rufi_gradient::gradient;
 use std::any::Any;
 use std::collections::HashMap;
 use std::iter;
 use std::rc::Rc;
-use std::str::FromStr;
-use utils::{DeviceState, Topology};
+use utils::{run_on_topology, DeviceState, Topology};
 
 fn setup_test_topology(devices: Vec<i32>) -> Topology {
     /* Set up a simple topology that will be used for these tests.
@@ -63,48 +68,6 @@ fn add_source(topology: &mut Topology, source: i32) {
     topology.states.insert(source, source_state);
 }
 
-fn run_on_device<A, F: Copy>(program: F, mut topology: Topology, d: i32) -> Topology
-where
-    F: Fn(&mut RoundVM) -> A,
-    A: Clone + 'static + FromStr,
-{
-    // Setup the VM
-    let curr = topology.states.get(&d).unwrap().clone();
-    let ctx = Context::new(d, curr.local_sensor, curr.nbr_sensor, curr.exports);
-    let mut vm = RoundVM::new(ctx);
-    vm.new_export_stack();
-    // Run the program
-    let _ = round(&mut vm, program);
-    // Update the topology with the new exports
-    let mut to_update = topology.states.get(&d).unwrap().clone();
-    to_update.update_exports(d, vm.export_data().clone());
-    // Update the exports of the neighbors, simulating the message passing
-    to_update
-        .nbr_sensor
-        .get(&sensor("nbr_range"))
-        .unwrap()
-        .keys()
-        .for_each(|nbr| {
-            let mut nbr_state = topology.states.get(nbr).unwrap().clone();
-            nbr_state.update_exports(d, to_update.exports.get(&d).unwrap().clone());
-            topology.states.insert(nbr.clone(), nbr_state);
-        });
-    topology.states.insert(d, to_update);
-    topology
-}
-
-fn run_on_topology<A, F>(program: F, mut topology: Topology, scheduling: &Vec<i32>) -> Topology
-where
-    F: Fn(&mut RoundVM) -> A + Copy,
-    A: Clone + 'static + FromStr,
-{
-    // For each device in the provided scheduling, run the program on the device.
-    for d in scheduling {
-        topology = run_on_device(program, topology, d.clone());
-    }
-    topology
-}
-
 #[test]
 fn test_single_source() {
     let devices = vec![1, 2, 3, 4, 5];
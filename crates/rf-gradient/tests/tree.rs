@@ -0,0 +1,99 @@
+mod utils;
+
+use rf_core::vm::round_vm::RoundVM;
+use rufi_gradient::tree::{spanning_tree, TreeNode};
+use std::collections::HashMap;
+use utils::{grid_topology, hop_distances, line_topology, run_on_topology, sense_flag, Topology};
+
+fn tree(vm: &mut RoundVM) -> TreeNode {
+    let root = sense_flag(vm, "root");
+    spanning_tree(vm, root)
+}
+
+/// Check that the nodes form a spanning tree of the topology rooted at `root`, along shortest paths.
+fn assert_spanning_tree(topology: &Topology, nodes: &HashMap<i32, TreeNode>, root: i32) {
+    let distances = hop_distances(topology, root);
+    for (d, node) in nodes {
+        assert_eq!(node.depth, distances[d], "depth of device {}", d);
+        if *d == root {
+            assert!(node.is_root());
+            assert_eq!(node.parent, i32::MAX);
+        } else {
+            let parent = &nodes[&node.parent];
+            assert_eq!(parent.depth, node.depth - 1, "parent of device {}", d);
+            assert!(
+                parent.children.contains(d),
+                "children of device {}",
+                node.parent
+            );
+        }
+        for child in &node.children {
+            assert_eq!(nodes[child].parent, *d, "parent of device {}", child);
+        }
+    }
+}
+
+#[test]
+fn test_tree_on_line() {
+    let mut topology = line_topology(5);
+    topology.set_local_sensor(3, "root", true);
+    let scheduling = topology.scheduling(10);
+    let final_topology = run_on_topology(tree, topology, &scheduling);
+    let nodes = final_topology.results::<TreeNode>();
+    assert_eq!(nodes[&3].children, vec![2, 4]);
+    assert!(nodes[&1].is_leaf());
+    assert!(nodes[&5].is_leaf());
+    assert_spanning_tree(&final_topology, &nodes, 3);
+}
+
+#[test]
+fn test_tree_on_grid() {
+    let mut topology = grid_topology(4, 4);
+    topology.set_local_sensor(1, "root", true);
+    let scheduling = topology.scheduling(15);
+    let final_topology = run_on_topology(tree, topology, &scheduling);
+    let nodes = final_topology.results::<TreeNode>();
+    // Ties are broken by the lowest id
+    assert_eq!(nodes[&6].parent, 2);
+    assert_spanning_tree(&final_topology, &nodes, 1);
+}
+
+#[test]
+fn test_tree_repair() {
+    /* Remove device 2 from the grid, so that device 3 must reach the root through device 6.
+     *  [1] -- [2] -- [3]
+     *   |      |      |
+     *  [4] -- [5] -- [6]
+     *   |      |      |
+     *  [7] -- [8] -- [9]
+     */
+    let mut topology = grid_topology(3, 3);
+    topology.set_local_sensor(1, "root", true);
+    let scheduling = topology.scheduling(10);
+    let mut topology = run_on_topology(tree, topology, &scheduling);
+    assert_eq!(topology.results::<TreeNode>()[&3].parent, 2);
+
+    topology.remove_device(2);
+    let scheduling = topology.scheduling(15);
+    let final_topology = run_on_topology(tree, topology, &scheduling);
+    let nodes = final_topology.results::<TreeNode>();
+    assert_eq!(nodes[&3].parent, 6);
+    assert_eq!(nodes[&3].depth, 4);
+    assert_spanning_tree(&final_topology, &nodes, 1);
+}
+
+#[test]
+fn test_tree_node_serialization() {
+    let node = TreeNode {
+        parent: 2,
+        children: vec![4, 7],
+        depth: 3,
+    };
+    assert_eq!(node.to_string().parse::<TreeNode>().unwrap(), node);
+    let root = TreeNode {
+        parent: i32::MAX,
+        children: vec![],
+        depth: 0,
+    };
+    assert_eq!(root.to_string().parse::<TreeNode>().unwrap(), root);
+}
@@ -1,8 +1,18 @@
+#![allow(dead_code)]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rf_core::context::Context;
 use rf_core::export::Export;
-use rf_core::sensor_id::SensorId;
+use rf_core::lang::execution::round;
+use rf_core::sensor_id::{sensor, SensorId};
+use rf_core::vm::round_vm::RoundVM;
 use std::any::Any;
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::iter;
 use std::rc::Rc;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct DeviceState {
@@ -28,4 +38,310 @@ impl Topology {
     pub fn new(devices: Vec<i32>, states: HashMap<i32, DeviceState>) -> Self {
         Topology { devices, states }
     }
+
+    /// Set the value of a local sensor of the given device.
+    pub fn set_local_sensor<A: 'static>(&mut self, device: i32, name: &str, value: A) {
+        if let Some(state) = self.states.get_mut(&device) {
+            state
+                .local_sensor
+                .insert(sensor(name), Rc::new(Box::new(value) as Box<dyn Any>));
+        }
+    }
+
+    /// Set the value of a neighbouring sensor of the given device for each of the given neighbours.
+    pub fn set_nbr_sensor<A: 'static>(&mut self, device: i32, name: &str, values: HashMap<i32, A>) {
+        if let Some(state) = self.states.get_mut(&device) {
+            let values = values
+                .into_iter()
+                .map(|(nbr, value)| (nbr, Rc::new(Box::new(value) as Box<dyn Any>)))
+                .collect();
+            state.nbr_sensor.insert(sensor(name), values);
+        }
+    }
+
+    /// Simulate synchronous rounds lasting the given number of seconds, setting the "delta_time"
+    /// local sensor and the "nbr_lag" neighbouring sensor of every device.
+    pub fn set_round_duration(&mut self, seconds: f64) {
+        for state in self.states.values_mut() {
+            state.local_sensor.insert(
+                sensor("delta_time"),
+                Rc::new(Box::new(seconds) as Box<dyn Any>),
+            );
+            let lags = state.nbr_sensor[&sensor("nbr_range")]
+                .keys()
+                .map(|nbr| (*nbr, Rc::new(Box::new(seconds) as Box<dyn Any>)))
+                .collect();
+            state.nbr_sensor.insert(sensor("nbr_lag"), lags);
+        }
+    }
+
+    /// Simulate the failure of a device, removing it from the topology and from the neighbourhood
+    /// of every other device.
+    pub fn remove_device(&mut self, device: i32) {
+        self.devices.retain(|d| *d != device);
+        self.states.remove(&device);
+        self.states.values_mut().for_each(|state| {
+            state.exports.remove(&device);
+            state.nbr_sensor.values_mut().for_each(|values| {
+                values.remove(&device);
+            });
+        });
+    }
+
+    /// Obtain the root value exported by each device.
+    pub fn results<A: 'static + FromStr + Clone>(&self) -> HashMap<i32, A> {
+        self.states
+            .iter()
+            .map(|(d, s)| (*d, s.exports.get(d).unwrap().root::<A>()))
+            .collect()
+    }
+
+    /// Build a scheduling in which every device runs `rounds` times, in order.
+    pub fn scheduling(&self, rounds: usize) -> Vec<i32> {
+        iter::repeat_n(self.devices.clone(), rounds)
+            .flatten()
+            .collect()
+    }
+}
+
+/// Build a topology from an adjacency map, where the "nbr_range" neighbouring sensor holds the
+/// given distance for each neighbour and 0 for the device itself.
+fn topology_from_adjacency(
+    devices: Vec<i32>,
+    adjacency: HashMap<i32, Vec<(i32, i32)>>,
+) -> Topology {
+    let states: HashMap<i32, DeviceState> = devices
+        .iter()
+        .map(|d| {
+            let nbr_range: HashMap<i32, Rc<Box<dyn Any>>> = adjacency
+                .get(d)
+                .unwrap()
+                .iter()
+                .chain(iter::once(&(*d, 0)))
+                .map(|(n, range)| (*n, Rc::new(Box::new(*range) as Box<dyn Any>)))
+                .collect();
+            let state = DeviceState {
+                self_id: *d,
+                exports: HashMap::new(),
+                local_sensor: HashMap::new(),
+                nbr_sensor: HashMap::from([(sensor("nbr_range"), nbr_range)]),
+            };
+            (*d, state)
+        })
+        .collect();
+    Topology::new(devices, states)
+}
+
+/// Set up a line topology of `n` devices: [1] -- [2] -- ... -- [n].
+pub fn line_topology(n: i32) -> Topology {
+    let devices: Vec<i32> = (1..=n).collect();
+    let adjacency = devices
+        .iter()
+        .map(|d| {
+            let nbrs = vec![(*d - 1, 1), (*d + 1, 1)]
+                .into_iter()
+                .filter(|(nbr, _)| *nbr > 0 && *nbr <= n)
+                .collect();
+            (*d, nbrs)
+        })
+        .collect();
+    topology_from_adjacency(devices, adjacency)
+}
+
+/// Set up a ring topology of `n` devices, where device `n` is connected to device 1.
+pub fn ring_topology(n: i32) -> Topology {
+    let devices: Vec<i32> = (1..=n).collect();
+    let adjacency = devices
+        .iter()
+        .map(|d| {
+            let previous = if *d == 1 { n } else { *d - 1 };
+            let next = if *d == n { 1 } else { *d + 1 };
+            (*d, vec![(previous, 1), (next, 1)])
+        })
+        .collect();
+    topology_from_adjacency(devices, adjacency)
+}
+
+/// Set up a grid topology with the given number of rows and columns, where each device is
+/// connected to the devices above, below, on the left and on the right of it. Devices are numbered
+/// row by row, starting from 1.
+pub fn grid_topology(rows: i32, cols: i32) -> Topology {
+    let devices: Vec<i32> = (1..=rows * cols).collect();
+    let adjacency = devices
+        .iter()
+        .map(|d| {
+            let (row, col) = grid_position(*d, cols);
+            let nbrs = vec![
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ]
+            .into_iter()
+            .filter(|(r, c)| *r >= 0 && *r < rows && *c >= 0 && *c < cols)
+            .map(|(r, c)| (grid_id(r, c, cols), 1))
+            .collect();
+            (*d, nbrs)
+        })
+        .collect();
+    topology_from_adjacency(devices, adjacency)
+}
+
+/// Set up a random geometric topology of `n` devices scattered in a unit square, where devices
+/// closer than `radius` are connected. The same `seed` always produces the same topology, and it
+/// is also stored in the "seed" local sensor of every device, so that the random numbers drawn by
+/// the devices are the same too.
+pub fn random_topology(n: i32, radius: f64, seed: u64) -> Topology {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let devices: Vec<i32> = (1..=n).collect();
+    let positions: HashMap<i32, (f64, f64)> = devices
+        .iter()
+        .map(|d| (*d, (rng.gen::<f64>(), rng.gen::<f64>())))
+        .collect();
+    let adjacency = devices
+        .iter()
+        .map(|d| {
+            let (x, y) = positions[d];
+            let nbrs = devices
+                .iter()
+                .filter(|other| *other != d)
+                .filter(|other| {
+                    let (ox, oy) = positions[*other];
+                    ((x - ox).powi(2) + (y - oy).powi(2)).sqrt() < radius
+                })
+                .map(|other| (*other, 1))
+                .collect();
+            (*d, nbrs)
+        })
+        .collect();
+    let mut topology = topology_from_adjacency(devices.clone(), adjacency);
+    for d in devices {
+        topology.set_local_sensor(d, "seed", seed);
+    }
+    topology
+}
+
+/// Obtain the neighbours of the given device, itself excluded.
+pub fn neighbours(topology: &Topology, device: i32) -> Vec<i32> {
+    topology.states[&device].nbr_sensor[&sensor("nbr_range")]
+        .keys()
+        .filter(|nbr| **nbr != device)
+        .cloned()
+        .collect()
+}
+
+/// Obtain the id of the device at the given position of a grid with `cols` columns.
+pub fn grid_id(row: i32, col: i32, cols: i32) -> i32 {
+    row * cols + col + 1
+}
+
+/// Obtain the position of the given device inside a grid with `cols` columns.
+pub fn grid_position(device: i32, cols: i32) -> (i32, i32) {
+    ((device - 1) / cols, (device - 1) % cols)
+}
+
+/// The metric used in tests, which reads the "nbr_range" neighbouring sensor.
+pub fn nbr_range(vm: &mut RoundVM) -> f64 {
+    *vm.nbr_sense::<i32>(&sensor("nbr_range")).unwrap() as f64
+}
+
+/// Read a boolean local sensor of the current device, defaulting to false when absent.
+pub fn sense_flag(vm: &RoundVM, name: &str) -> bool {
+    vm.local_sense::<bool>(&sensor(name))
+        .cloned()
+        .unwrap_or(false)
+}
+
+pub fn run_on_device<A, F>(program: F, mut topology: Topology, d: i32) -> Topology
+where
+    F: Fn(&mut RoundVM) -> A + Copy,
+    A: Clone + 'static + FromStr + Display,
+{
+    // Setup the VM
+    let curr = topology.states.get(&d).unwrap().clone();
+    let ctx = Context::new(d, curr.local_sensor, curr.nbr_sensor, curr.exports);
+    let mut vm = RoundVM::new(ctx);
+    vm.new_export_stack();
+    // Run the program
+    let _ = round(&mut vm, program);
+    // Update the topology with the new exports
+    let mut to_update = topology.states.get(&d).unwrap().clone();
+    to_update.update_exports(d, vm.export_data().clone());
+    // Update the exports of the neighbors, simulating the message passing
+    to_update
+        .nbr_sensor
+        .get(&sensor("nbr_range"))
+        .unwrap()
+        .keys()
+        .for_each(|nbr| {
+            let mut nbr_state = topology.states.get(nbr).unwrap().clone();
+            nbr_state.update_exports(d, to_update.exports.get(&d).unwrap().clone());
+            topology.states.insert(*nbr, nbr_state);
+        });
+    topology.states.insert(d, to_update);
+    topology
+}
+
+pub fn run_on_topology<A, F>(program: F, mut topology: Topology, scheduling: &Vec<i32>) -> Topology
+where
+    F: Fn(&mut RoundVM) -> A + Copy,
+    A: Clone + 'static + FromStr + Display,
+{
+    // For each device in the provided scheduling, run the program on the device.
+    for d in scheduling {
+        topology = run_on_device(program, topology, *d);
+    }
+    topology
+}
+
+/// Run `rounds` synchronous rounds, in which every device computes its export from the exports of
+/// the previous round only.
+pub fn run_synchronously<A, F>(program: F, mut topology: Topology, rounds: usize) -> Topology
+where
+    F: Fn(&mut RoundVM) -> A + Copy,
+    A: Clone + 'static + FromStr + Display,
+{
+    for _ in 0..rounds {
+        let exports: Vec<(i32, Export)> = topology
+            .devices
+            .iter()
+            .map(|d| {
+                let computed = run_on_device(program, topology.clone(), *d);
+                (*d, computed.states[d].exports[d].clone())
+            })
+            .collect();
+        for (d, export) in exports {
+            let nbrs: Vec<i32> = topology.states[&d].nbr_sensor[&sensor("nbr_range")]
+                .keys()
+                .cloned()
+                .collect();
+            for nbr in nbrs {
+                if let Some(state) = topology.states.get_mut(&nbr) {
+                    state.update_exports(d, export.clone());
+                }
+            }
+        }
+    }
+    topology
+}
+
+/// Compute the hop distance from the given device to every device reachable from it.
+pub fn hop_distances(topology: &Topology, from: i32) -> HashMap<i32, i32> {
+    let mut distances = HashMap::from([(from, 0)]);
+    let mut frontier = vec![from];
+    while !frontier.is_empty() {
+        let mut next = vec![];
+        for d in frontier {
+            let distance = distances[&d];
+            let nbrs = topology.states[&d].nbr_sensor[&sensor("nbr_range")].keys();
+            for nbr in nbrs {
+                if !distances.contains_key(nbr) {
+                    distances.insert(*nbr, distance + 1);
+                    next.push(*nbr);
+                }
+            }
+        }
+        frontier = next;
+    }
+    distances
 }
@@ -1,18 +1,20 @@
+use async_trait::async_trait;
 use rufi::core::context::{Context, NbrSensors};
+use rufi::core::export::Export;
 use rufi::core::sensor_id::{sensor, SensorId};
+use rufi::distributed::discovery::asynchronous::Discovery;
 use rufi::distributed::discovery::nbr_sensors_setup::NbrSensorSetup;
-use rufi::distributed::discovery::Discovery;
 use rufi::distributed::impls::mailbox::MailboxFactory;
-use rufi::distributed::impls::network::SyncMQTTNetwork;
-use rufi::distributed::impls::time::TimeImpl;
-use rufi::distributed::platform::sync::RuFiPlatform;
+use rufi::distributed::impls::network::AsyncMQTTNetwork;
+use rufi::distributed::impls::scheduler::JitteredRate;
+use rufi::distributed::impls::time::AsyncTimeImpl;
+use rufi::distributed::platform::asynchronous::RuFiPlatform;
 use rufi::programs::gradient;
 use rumqttc::MqttOptions;
 use std::any::Any;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::time::Duration;
-use rufi::core::export::Export;
 
 #[derive(Debug, Default)]
 struct Arguments {
@@ -38,12 +40,13 @@ impl Arguments {
 
 struct MockDiscovery(i32);
 
+#[async_trait]
 impl Discovery for MockDiscovery {
-    fn discover_neighbors(&self) -> Vec<i32> {
+    async fn discover_neighbors(&self) -> Vec<i32> {
         let self_id = self.0;
         vec![self_id - 1, self_id, self_id + 1]
             .into_iter()
-            .filter(|n| (n > &0 && n < &6))
+            .filter(|n| n > &0 && n < &6)
             .collect()
     }
 }
@@ -76,7 +79,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
      *  Topology: [1] -- [2] -- [3] -- [4] -- [5].
      */
     let discovery = MockDiscovery(self_id);
-    let nbrs = discovery.discover_neighbors();
+    let nbrs = discovery.discover_neighbors().await;
 
     let setup = MockSetup {};
 
@@ -98,16 +101,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut mqttoptions =
         MqttOptions::new(format!("device#{}", self_id), "test.mosquitto.org", 1883);
     mqttoptions.set_keep_alive(Duration::from_secs(5));
-    let network = SyncMQTTNetwork::new(mqttoptions, nbrs.clone(), 10);
+    let network = AsyncMQTTNetwork::new(mqttoptions, nbrs.clone(), 10).await;
     // Setup the mailbox
     let mailbox = MailboxFactory::memory_less();
 
-    let time = TimeImpl::new();
+    let time = AsyncTimeImpl::new();
+    // Execute a round every second, spread over 100 milliseconds
+    let scheduler = JitteredRate::new(Duration::from_secs(1), Duration::from_millis(100));
 
-    let debug_hook = |export: &Export| {
+    let debug_hook = |_export: &Export| {
         //println!("EXPORT: {:?}\n OUTPUT:{:?}", export, export.root());
     };
     // Setup the platform and run the program
-    RuFiPlatform::new(mailbox, network?, context, discovery, setup, time, vec![debug_hook])
-        .run_forever(gradient)
+    RuFiPlatform::new(
+        mailbox,
+        network?,
+        context,
+        discovery,
+        setup,
+        time,
+        scheduler,
+        vec![debug_hook],
+    )
+    .run_forever(gradient)
+    .await
 }
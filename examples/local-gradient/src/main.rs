@@ -6,7 +6,6 @@ use rufi::core::vm::round_vm::RoundVM;
 use rufi::programs::gradient;
 use std::any::Any;
 use std::collections::HashMap;
-use std::iter;
 use std::rc::Rc;
 
 fn main() {
@@ -17,9 +16,9 @@ fn main() {
     let mut states: HashMap<i32, DeviceState> = devices
         .iter()
         .map(|d| {
-            let nbrs: Vec<i32> = vec![d.clone() - 1, d.clone(), d.clone() + 1]
+            let nbrs: Vec<i32> = vec![*d - 1, *d, *d + 1]
                 .into_iter()
-                .filter(|n| (n > &0 && n < &6))
+                .filter(|n| n > &0 && n < &6)
                 .collect();
             // In this example, we set the source to be device 2.
             let local_sensor: HashMap<SensorId, Rc<Box<dyn Any>>> =
@@ -29,25 +28,20 @@ fn main() {
             let nbr_sensor: HashMap<SensorId, HashMap<i32, Rc<Box<dyn Any>>>> = HashMap::from([(
                 sensor("nbr_range"),
                 nbrs.iter()
-                    .map(|n| {
-                        (
-                            n.clone(),
-                            Rc::new(Box::new(i32::abs(d - n)) as Box<dyn Any>),
-                        )
-                    })
+                    .map(|n| (*n, Rc::new(Box::new(i32::abs(d - n)) as Box<dyn Any>)))
                     .collect(),
             )]);
             let state = DeviceState {
-                self_id: d.clone(),
+                self_id: *d,
                 exports: HashMap::new(),
                 local_sensor,
                 nbr_sensor,
             };
-            (d.clone(), state)
+            (*d, state)
         })
         .collect();
 
-    let scheduling: Vec<i32> = iter::repeat(devices).take(5).flatten().collect();
+    let scheduling: Vec<i32> = std::iter::repeat_n(devices, 5).flatten().collect();
 
     // For each device in the provided scheduling, run the program on the device.
     for d in scheduling {
@@ -70,7 +64,7 @@ fn main() {
             .for_each(|nbr| {
                 let mut nbr_state = states.get(nbr).unwrap().clone();
                 nbr_state.update_exports(d, to_update.exports.get(&d).unwrap().clone());
-                states.insert(nbr.clone(), nbr_state);
+                states.insert(*nbr, nbr_state);
             });
         // Update the topology with the new state
         states.insert(d, to_update);